@@ -0,0 +1,225 @@
+use crate::*;
+use enemy_targeting::HostileTarget;
+
+/// What an enemy does while in a state, applied once when the state is entered
+#[derive(Reflect, Clone, Debug, PartialEq)]
+pub enum BehaviorAction {
+    Approach,
+    /// closes in from a free slot on a ring of `radius` around the target
+    Outflank {
+        radius: f32,
+    },
+    Retreat,
+    Strafe,
+    /// stands still and shoots
+    Attack,
+    Wait,
+    /// runs from its target without shooting
+    Flee,
+    /// hands its target to enemies with a behavior within `radius`
+    CallAllies {
+        radius: f32,
+    },
+}
+
+#[derive(Reflect, Clone, Debug)]
+pub enum BehaviorCondition {
+    Always,
+    TargetWithin(f32),
+    TargetBeyond(f32),
+    /// life as a fraction of [`health::MaxLife`]
+    HealthBelow(f32),
+    /// seconds spent in the current state
+    After(f32),
+    /// another enemy called for help
+    Called,
+}
+
+#[derive(Reflect, Clone, Debug)]
+pub struct BehaviorTransition {
+    pub to: String,
+    pub when: BehaviorCondition,
+}
+
+#[derive(Reflect, Clone, Debug)]
+pub struct BehaviorState {
+    pub name: String,
+    pub action: BehaviorAction,
+    pub transitions: Vec<BehaviorTransition>,
+}
+
+/// State machine driving [`MoveTargetingType`] and shooting of an enemy.
+/// The first state is the initial one, `global` transitions are checked from any state.
+#[derive(Component, Reflect, Default, Debug)]
+#[reflect(Component)]
+pub struct Behavior {
+    pub states: Vec<BehaviorState>,
+    pub global: Vec<BehaviorTransition>,
+    #[reflect(ignore)]
+    current: Option<usize>,
+    #[reflect(ignore)]
+    elapsed: f32,
+    #[reflect(ignore)]
+    called: bool,
+}
+impl Behavior {
+    pub fn current_state(&self) -> Option<&BehaviorState> {
+        self.current.map(|i| &self.states[i])
+    }
+}
+
+/// Enemy weapons stay silent whatever their [`enemy_targeting::FireRange`] says
+#[derive(Component)]
+pub struct HoldFire;
+
+/// Sent on every state change so behaviors can be debugged
+#[derive(Event, Debug)]
+pub struct BehaviorTransitionEvent {
+    pub entity: Entity,
+    pub from: Option<String>,
+    pub to: String,
+}
+
+struct Context {
+    target_distance: Option<f32>,
+    health: f32,
+}
+
+impl BehaviorCondition {
+    fn holds(&self, behavior: &Behavior, context: &Context) -> bool {
+        match self {
+            BehaviorCondition::Always => true,
+            BehaviorCondition::TargetWithin(distance) => {
+                context.target_distance.is_some_and(|d| d <= *distance)
+            }
+            BehaviorCondition::TargetBeyond(distance) => {
+                context.target_distance.map_or(true, |d| d > *distance)
+            }
+            BehaviorCondition::HealthBelow(part) => context.health < *part,
+            BehaviorCondition::After(seconds) => behavior.elapsed >= *seconds,
+            BehaviorCondition::Called => behavior.called,
+        }
+    }
+}
+
+fn apply_action(commands: &mut Commands, entity: Entity, action: &BehaviorAction) {
+    let mut entity_commands = commands.entity(entity);
+    match action {
+        BehaviorAction::Approach => {
+            entity_commands
+                .insert(MoveTargetingType::Chase)
+                .remove::<HoldFire>();
+        }
+        BehaviorAction::Outflank { radius } => {
+            entity_commands
+                .insert(MoveTargetingType::Outflank { radius: *radius })
+                .remove::<HoldFire>();
+        }
+        BehaviorAction::Retreat => {
+            entity_commands
+                .insert(MoveTargetingType::Retreat)
+                .remove::<HoldFire>();
+        }
+        BehaviorAction::Strafe => {
+            entity_commands
+                .insert(MoveTargetingType::Strafe)
+                .remove::<HoldFire>();
+        }
+        BehaviorAction::Attack => {
+            entity_commands
+                .remove::<(MoveTargetingType, Movement, HoldFire)>()
+                .insert(LinearVelocity(Vec2::ZERO));
+        }
+        BehaviorAction::Wait | BehaviorAction::CallAllies { .. } => {
+            entity_commands
+                .remove::<(MoveTargetingType, Movement)>()
+                .insert((LinearVelocity(Vec2::ZERO), HoldFire));
+        }
+        BehaviorAction::Flee => {
+            entity_commands.insert((MoveTargetingType::Retreat, HoldFire));
+        }
+    }
+}
+
+pub fn behavior_system(
+    mut commands: Commands,
+    mut behaviors: Query<(
+        Entity,
+        &mut Behavior,
+        &Life,
+        Option<&health::MaxLife>,
+        &Transform,
+        Option<&HostileTarget>,
+    )>,
+    targets: Query<&Transform, With<Life>>,
+    mut writer: EventWriter<BehaviorTransitionEvent>,
+    time: Res<Time>,
+) {
+    let mut calls = vec![];
+    behaviors.for_each_mut(
+        |(entity, mut behavior, life, max_life, transform, target)| {
+            if behavior.states.is_empty() {
+                return;
+            }
+            behavior.elapsed += time.delta_seconds();
+            let context = Context {
+                target_distance: target
+                    .and_then(|HostileTarget(target)| targets.get(*target).ok())
+                    .map(|target_transform| {
+                        transform.translation.distance(target_transform.translation)
+                    }),
+                health: max_life.map_or(1., |max_life| life.0 / max_life.0),
+            };
+
+            let next = match behavior.current {
+                None => Some(0),
+                Some(current) => behavior
+                    .global
+                    .iter()
+                    .chain(behavior.states[current].transitions.iter())
+                    .filter(|transition| transition.when.holds(&behavior, &context))
+                    .filter_map(|transition| {
+                        behavior
+                            .states
+                            .iter()
+                            .position(|state| state.name == transition.to)
+                    })
+                    .find(|next| *next != current),
+            };
+            behavior.called = false;
+            let Some(next) = next else {
+                return;
+            };
+
+            writer.send(BehaviorTransitionEvent {
+                entity,
+                from: behavior.current_state().map(|state| state.name.clone()),
+                to: behavior.states[next].name.clone(),
+            });
+            behavior.current = Some(next);
+            behavior.elapsed = 0.;
+            let action = &behavior.states[next].action;
+            apply_action(&mut commands, entity, action);
+            if let (BehaviorAction::CallAllies { radius }, Some(target)) = (action, target) {
+                calls.push((entity, transform.translation, *radius, target.0));
+            }
+        },
+    );
+
+    calls
+        .into_iter()
+        .for_each(|(caller, position, radius, target)| {
+            behaviors.for_each_mut(|(entity, mut behavior, _, _, transform, _)| {
+                if entity != caller && transform.translation.distance(position) <= radius {
+                    behavior.called = true;
+                    commands.entity(entity).insert(HostileTarget(target));
+                }
+            });
+        });
+}
+
+pub fn behavior_log_system(mut reader: EventReader<BehaviorTransitionEvent>) {
+    reader.read().for_each(|event| {
+        debug!("behavior: {:?}", event);
+    });
+}
@@ -0,0 +1,417 @@
+use std::f32::consts::TAU;
+
+use bevy::ecs::system::EntityCommands;
+
+use crate::*;
+use enemies::EnemyType;
+use enemy_targeting::HostileTarget;
+use forced_moving::{Dashing, ForcedMove};
+
+#[derive(Clone, Debug)]
+pub enum BossAttack {
+    /// a ring of `count` bullets, turned by `spin` radians on every use
+    Pattern {
+        count: u32,
+        speed: f32,
+        damage: f32,
+        spin: f32,
+    },
+    /// rushes at the target
+    Dash { speed: f32, time: f32 },
+    /// calls in enemies of a registered type around itself
+    Summon {
+        enemy: String,
+        count: u32,
+        strength: u32,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct BossPhase {
+    /// the phase starts once life drops to this part of the full life
+    pub health_threshold: f32,
+    /// used in turn, one every `attack_interval` seconds
+    pub attacks: Vec<BossAttack>,
+    pub attack_interval: f32,
+    pub speed: f32,
+}
+
+/// Everything a boss is made of, phases are ordered by decreasing threshold
+#[derive(Component, Clone, Debug)]
+pub struct BossDefinition {
+    pub name: String,
+    pub life: f32,
+    pub size: f32,
+    pub color: Color,
+    /// seconds of invulnerability between two phases
+    pub transition_time: f32,
+    pub score: u32,
+    pub experience: u32,
+    pub phases: Vec<BossPhase>,
+}
+
+impl EnemyType for BossDefinition {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn insert(&self, enemy: &mut EntityCommands) {
+        enemy.insert(self.clone());
+    }
+}
+
+/// Runtime state of a spawned boss
+#[derive(Component)]
+pub struct Boss {
+    pub phase: usize,
+    attack_timer: Timer,
+    next_attack: usize,
+    turn: f32,
+    dash: Option<Timer>,
+}
+
+#[derive(Event, Debug)]
+pub struct BossPhaseEvent {
+    pub boss: Entity,
+    pub phase: usize,
+}
+
+/// Root of the health bar shown for `boss`
+#[derive(Component)]
+pub struct BossHealthBar {
+    pub boss: Entity,
+}
+#[derive(Component)]
+pub struct BossHealthFill;
+
+pub fn boss_initializer(
+    mut commands: Commands,
+    to_initialize: Query<(Entity, &InitPosition, &BossDefinition), With<Character>>,
+) {
+    to_initialize.for_each(|(entity, initial_pos, definition)| {
+        let first = &definition.phases[0];
+        commands
+            .entity(entity)
+            .insert((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: definition.color,
+                        custom_size: Some(Vec2::splat(definition.size)),
+                        ..Default::default()
+                    },
+                    transform: initial_pos.0,
+                    ..Default::default()
+                },
+                Life(definition.life),
+                health::MaxLife(definition.life),
+                enemies::ContactDamage(25.),
+                Aims(Vec2::ZERO),
+                movements::Movable { speed: first.speed },
+                Collider::ball(definition.size / 2.),
+                CollisionLayers::new(
+                    [Layer::Enemy],
+                    [
+                        Layer::Player,
+                        Layer::PlayerBullet,
+                        Layer::Wall,
+                        Layer::Blast,
+                    ],
+                ),
+                AimTargetingType::AimCurrent,
+                MoveTargetingType::Chase,
+                enemies::Reward {
+                    score: definition.score,
+                    experience: definition.experience,
+                },
+                Boss {
+                    phase: 0,
+                    attack_timer: Timer::from_seconds(first.attack_interval, TimerMode::Repeating),
+                    next_attack: 0,
+                    turn: 0.,
+                    dash: None,
+                },
+            ))
+            .remove::<InitPosition>();
+
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(12.),
+                        left: Val::Percent(20.),
+                        width: Val::Percent(60.),
+                        height: Val::Px(12.),
+                        ..Default::default()
+                    },
+                    background_color: Color::rgba(0., 0., 0., 0.6).into(),
+                    ..Default::default()
+                },
+                BossHealthBar { boss: entity },
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Percent(100.),
+                            height: Val::Percent(100.),
+                            ..Default::default()
+                        },
+                        background_color: definition.color.into(),
+                        ..Default::default()
+                    },
+                    BossHealthFill,
+                ));
+            });
+    });
+}
+
+/// Moves bosses through their phases and fires their attacks
+pub fn boss_system(
+    mut commands: Commands,
+    mut bosses: Query<(
+        Entity,
+        &BossDefinition,
+        &mut Boss,
+        &Life,
+        &Transform,
+        &mut movements::Movable,
+        Option<&HostileTarget>,
+        Option<&LevelRef>,
+        Has<Invulnerable>,
+    )>,
+    targets: Query<&Transform, With<Life>>,
+    levels: Query<&LevelInfo>,
+    mut phase_writer: EventWriter<BossPhaseEvent>,
+    mut bullet_writer: EventWriter<bullets::BulletSpawnEvent>,
+    time: Res<Time>,
+) {
+    bosses.for_each_mut(
+        |(
+            entity,
+            definition,
+            mut boss,
+            life,
+            transform,
+            mut movable,
+            target,
+            level,
+            invulnerable,
+        )| {
+            let health = life.0 / definition.life;
+            let next_phase = definition
+                .phases
+                .iter()
+                .rposition(|phase| health <= phase.health_threshold)
+                .unwrap_or(0);
+            if next_phase > boss.phase {
+                let phase = &definition.phases[next_phase];
+                boss.phase = next_phase;
+                boss.attack_timer =
+                    Timer::from_seconds(phase.attack_interval, TimerMode::Repeating);
+                boss.next_attack = 0;
+                movable.speed = phase.speed;
+                commands
+                    .entity(entity)
+                    .insert(Invulnerable(Timer::from_seconds(
+                        definition.transition_time,
+                        TimerMode::Once,
+                    )));
+                phase_writer.send(BossPhaseEvent {
+                    boss: entity,
+                    phase: next_phase,
+                });
+                return;
+            }
+
+            if let Some(dash) = boss.dash.as_mut() {
+                if dash.tick(time.delta()).just_finished() {
+                    commands.entity(entity).remove::<(ForcedMove, Dashing)>();
+                    boss.dash = None;
+                }
+                return;
+            }
+            if invulnerable || !boss.attack_timer.tick(time.delta()).just_finished() {
+                return;
+            }
+
+            let phase = &definition.phases[boss.phase];
+            let Some(attack) = phase
+                .attacks
+                .get(boss.next_attack % phase.attacks.len().max(1))
+            else {
+                return;
+            };
+            boss.next_attack += 1;
+            let position = transform.translation.truncate();
+            match attack {
+                BossAttack::Pattern {
+                    count,
+                    speed,
+                    damage,
+                    spin,
+                } => {
+                    boss.turn += spin;
+                    (0..*count).for_each(|i| {
+                        let angle = boss.turn + i as f32 * TAU / *count as f32;
+                        bullet_writer.send(bullets::BulletSpawnEvent {
+                            shooter: Transform::from_translation(transform.translation)
+                                .with_rotation(Quat::from_rotation_z(angle)),
+                            by: entity,
+                            // patterns are not shot with a weapon
+                            with: entity,
+                            bullet: Bullet {
+                                life_time: 3.,
+                                endurance: 1.,
+                                hit_limit: 1.,
+                                speed: *speed,
+                                cooldown: 0.,
+                                damage: *damage,
+                            },
+                            bullet_type: Box::new(bullets::lane_shot::LaneShot),
+                            bullet_extras: vec![],
+                            generation: 0,
+                        });
+                    });
+                }
+                BossAttack::Dash { speed, time } => {
+                    let Some(target_position) = target
+                        .and_then(|HostileTarget(target)| targets.get(*target).ok())
+                        .map(|target_transform| target_transform.translation.truncate())
+                    else {
+                        return;
+                    };
+                    commands.entity(entity).insert((
+                        ForcedMove {
+                            direction: (target_position - position).normalize_or_zero(),
+                            speed: *speed,
+                        },
+                        Dashing,
+                    ));
+                    boss.dash = Some(Timer::from_seconds(*time, TimerMode::Once));
+                }
+                BossAttack::Summon {
+                    enemy,
+                    count,
+                    strength,
+                } => {
+                    let Some((LevelRef(level), level_info)) =
+                        level.and_then(|level| Some((level, levels.get(level.0).ok()?)))
+                    else {
+                        return;
+                    };
+                    // summons appear around the boss wherever players are,
+                    // the warning is what gives them time to step away
+                    (0..*count).for_each(|i| {
+                        let angle = i as f32 * TAU / *count as f32;
+                        let offset = Vec2::from_angle(angle) * definition.size;
+                        levels::spawn_warning(
+                            &mut commands,
+                            position + offset,
+                            levels::SpawnWarning {
+                                timer: Timer::from_seconds(
+                                    level_info.spawn_warning_time,
+                                    TimerMode::Once,
+                                ),
+                                enemy: enemy.clone(),
+                                class: EnemyClass::Normal(*strength),
+                                affixes: vec![],
+                            },
+                            *level,
+                        );
+                    });
+                }
+            }
+        },
+    );
+}
+
+pub fn boss_health_bar_system(
+    mut commands: Commands,
+    bars: Query<(Entity, &BossHealthBar, &Children)>,
+    mut fills: Query<&mut Style, With<BossHealthFill>>,
+    bosses: Query<(&Life, &BossDefinition)>,
+) {
+    bars.for_each(|(entity, BossHealthBar { boss }, children)| {
+        let Ok((life, definition)) = bosses.get(*boss) else {
+            commands.entity(entity).despawn_recursive();
+            return;
+        };
+        let health = (life.0 / definition.life).clamp(0., 1.);
+        let mut fills = fills.iter_many_mut(children);
+        while let Some(mut style) = fills.fetch_next() {
+            style.width = Val::Percent(health * 100.);
+        }
+    });
+}
+
+pub fn boss_phase_log_system(mut reader: EventReader<BossPhaseEvent>) {
+    reader.read().for_each(|event| {
+        info!("boss: {:?}", event);
+    });
+}
+
+/// Keeps its distance with rings of bullets, then starts rushing and calling for help
+pub fn warden() -> BossDefinition {
+    BossDefinition {
+        name: "warden".into(),
+        life: 2000.,
+        size: 64.,
+        color: Color::CRIMSON,
+        transition_time: 2.,
+        score: 1000,
+        experience: 300,
+        phases: vec![
+            BossPhase {
+                health_threshold: 1.,
+                attacks: vec![BossAttack::Pattern {
+                    count: 12,
+                    speed: 200.,
+                    damage: 10.,
+                    spin: 0.2,
+                }],
+                attack_interval: 1.5,
+                speed: 60.,
+            },
+            BossPhase {
+                health_threshold: 0.6,
+                attacks: vec![
+                    BossAttack::Pattern {
+                        count: 16,
+                        speed: 240.,
+                        damage: 10.,
+                        spin: 0.3,
+                    },
+                    BossAttack::Dash {
+                        speed: 600.,
+                        time: 0.5,
+                    },
+                ],
+                attack_interval: 1.2,
+                speed: 80.,
+            },
+            BossPhase {
+                health_threshold: 0.25,
+                attacks: vec![
+                    BossAttack::Summon {
+                        enemy: enemies::kamikaze_enemy::KamikazeEnemy::TEXT.into(),
+                        count: 4,
+                        strength: 2,
+                    },
+                    BossAttack::Pattern {
+                        count: 24,
+                        speed: 260.,
+                        damage: 12.,
+                        spin: 0.15,
+                    },
+                    BossAttack::Dash {
+                        speed: 700.,
+                        time: 0.5,
+                    },
+                ],
+                attack_interval: 1.,
+                speed: 100.,
+            },
+        ],
+    }
+}
@@ -24,7 +24,7 @@ pub(crate) struct BulletSpawnEvent {
     pub generation: usize,
 }
 #[derive(Component)]
-pub(crate) struct Shooter(Entity);
+pub(crate) struct Shooter(pub Entity);
 #[derive(Component)]
 pub(crate) struct InitPosition(pub Transform);
 
@@ -71,6 +71,15 @@ pub(crate) fn bullet_spawner(
                     [Layer::PlayerBullet],
                     [Layer::Enemy, Layer::EnemyBullet],
                 ));
+            } else if event
+                .bullet_extras
+                .iter()
+                .any(|extra| extra.as_reflect().is::<friendly_fire::FriendlyFire>())
+            {
+                bullet_ec.insert(CollisionLayers::new(
+                    [Layer::EnemyBullet, Layer::Blast],
+                    [Layer::Player, Layer::PlayerBullet, Layer::Enemy],
+                ));
             } else {
                 bullet_ec.insert(CollisionLayers::new(
                     [Layer::EnemyBullet],
@@ -94,7 +103,9 @@ pub(crate) fn bullet_succeed(
     mut weapons: Query<(&Weapon, &Parent)>,
 ) {
     reader.read().for_each(|event| {
-        let (weapon, shooter) = weapons.get_mut(event.weapon).unwrap();
+        let Ok((weapon, shooter)) = weapons.get_mut(event.weapon) else {
+            return;
+        };
         if let Some(next_bullet) = weapon.loads.get(event.generation) {
             writer.send(bullets::BulletSpawnEvent {
                 shooter: event.transform,
@@ -425,6 +436,17 @@ pub mod lazer_shot {
     }
 }
 
+pub mod friendly_fire {
+    use super::*;
+
+    /// Enemy bullets with this extra hit enemies as well
+    #[derive(Component, Reflect, Default, Clone, Copy)]
+    #[reflect(Component)]
+    pub(crate) struct FriendlyFire;
+
+    impl BulletExtra for FriendlyFire {}
+}
+
 pub mod shot_auto_targeting {
     use super::*;
     // TODO: AUTO TARGETING should work along with original shot, not creating new shot
@@ -447,3 +469,111 @@ pub mod shot_auto_targeting {
     }
 }
 
+pub mod burning {
+    use super::*;
+
+    /// Hits set the target on fire, dealing `damage_per_second` for `seconds`
+    #[derive(Component, Reflect, Default, Clone, Copy)]
+    #[reflect(Component)]
+    pub(crate) struct Burning {
+        pub damage_per_second: f32,
+        pub seconds: f32,
+    }
+
+    impl BulletExtra for Burning {}
+
+    /// Fire on a character, a new hit starts it over
+    #[derive(Component)]
+    pub(crate) struct Burn {
+        damage_per_second: f32,
+        source: Entity,
+        timer: Timer,
+    }
+
+    pub(crate) fn ignite_system(
+        mut commands: Commands,
+        mut reader: EventReader<BulletHitEvent>,
+        bullets: Query<(&Burning, &Shooter)>,
+        lives: Query<(), With<Life>>,
+    ) {
+        reader.read().for_each(|event| {
+            if event.kind != HitKind::Damaging || !lives.contains(event.target) {
+                return;
+            }
+            let Ok((burning, Shooter(shooter))) = bullets.get(event.bullet_entity) else {
+                return;
+            };
+            commands.entity(event.target).insert(Burn {
+                damage_per_second: burning.damage_per_second,
+                source: *shooter,
+                timer: Timer::from_seconds(burning.seconds, TimerMode::Once),
+            });
+        });
+    }
+
+    pub(crate) fn burn_system(
+        mut commands: Commands,
+        mut burns: Query<(Entity, &mut Burn)>,
+        mut writer: EventWriter<health::DamageEvent>,
+        time: Res<Time>,
+    ) {
+        burns.for_each_mut(|(entity, mut burn)| {
+            writer.send(health::DamageEvent {
+                target: entity,
+                source: Some(burn.source),
+                amount: burn.damage_per_second * time.delta_seconds(),
+            });
+            if burn.timer.tick(time.delta()).finished() {
+                commands.entity(entity).remove::<Burn>();
+            }
+        });
+    }
+}
+
+pub mod knockback {
+    use super::*;
+
+    /// Hits shock the target away from the shooter,
+    /// an `impact` of 1 is the push of bumping into another character
+    #[derive(Component, Reflect, Default, Clone, Copy)]
+    #[reflect(Component)]
+    pub(crate) struct Knockback {
+        pub impact: f32,
+    }
+
+    impl BulletExtra for Knockback {}
+
+    pub(crate) fn knockback_system(
+        mut commands: Commands,
+        mut reader: EventReader<BulletHitEvent>,
+        bullets: Query<(&Knockback, &Shooter)>,
+        // bosses don't get pushed around, dashes aren't interrupted
+        targets: Query<
+            (&Transform, Has<forced_moving::Dashing>),
+            (With<Character>, Without<bosses::Boss>),
+        >,
+    ) {
+        reader.read().for_each(|event| {
+            if event.kind != HitKind::Damaging {
+                return;
+            }
+            let Ok((knockback, Shooter(shooter))) = bullets.get(event.bullet_entity) else {
+                return;
+            };
+            let Ok([(shooter_transform, _), (target_transform, false)]) =
+                targets.get_many([*shooter, event.target])
+            else {
+                return;
+            };
+            let direction = (target_transform.translation - shooter_transform.translation)
+                .truncate()
+                .normalize_or_zero();
+            commands
+                .entity(event.target)
+                .insert(forced_moving::Shocked {
+                    impact: knockback.impact,
+                    direction,
+                });
+        });
+    }
+}
@@ -1,2 +1,3 @@
 pub const GAME_FIXED_TICK_PER_SECOND: f64 = 64.;
 
+pub const PLAYER_MAX_LIFE: f32 = 100.;
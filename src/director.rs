@@ -0,0 +1,224 @@
+use std::collections::VecDeque;
+
+use crate::*;
+
+/// Bounds a level sets for its director
+pub struct DirectorSettings {
+    /// seconds of damage taken and kills that are remembered
+    pub window: f32,
+    /// seconds between two decisions
+    pub decision_interval: f32,
+    pub min_pressure: f32,
+    pub max_pressure: f32,
+    /// how much pressure changes on one decision
+    pub pressure_step: f32,
+    /// total extra enemies the director may add to the level
+    pub max_extra_enemies: u32,
+    /// enemy type used for extra enemies
+    pub extra_enemy: String,
+    pub relief_heal: f32,
+    /// minimum seconds between two relief pickups
+    pub relief_cooldown: f32,
+}
+
+/// Watches how players are doing and raises or lowers the pressure of its level.
+/// The spawn interval of the level is divided by `pressure`.
+#[derive(Component)]
+pub struct Director {
+    pub settings: DirectorSettings,
+    pub pressure: f32,
+    extra_enemies: u32,
+    decision_timer: Timer,
+    relief_timer: Timer,
+    /// `(time, amount)` of damage taken by players
+    damages: VecDeque<(f32, f32)>,
+    /// time of each enemy kill
+    kills: VecDeque<f32>,
+    last_player_life: Option<f32>,
+}
+impl Director {
+    pub fn new(settings: DirectorSettings) -> Self {
+        Self {
+            pressure: 1.,
+            extra_enemies: 0,
+            decision_timer: Timer::from_seconds(settings.decision_interval, TimerMode::Repeating),
+            relief_timer: Timer::from_seconds(settings.relief_cooldown, TimerMode::Once),
+            damages: VecDeque::new(),
+            kills: VecDeque::new(),
+            last_player_life: None,
+            settings,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DirectorDecision {
+    RaisePressure(f32),
+    LowerPressure(f32),
+    InsertEnemies(u32),
+    GrantRelief(Vec2),
+}
+
+/// Sent for every decision so the director can be tuned
+#[derive(Event, Debug)]
+pub struct DirectorEvent {
+    pub level: Entity,
+    pub decision: DirectorDecision,
+    /// damage taken per second in the window
+    pub damage_rate: f32,
+    /// kills per second in the window
+    pub kill_rate: f32,
+    pub lowest_health: f32,
+}
+
+/// players doing worse than this get relief
+const STRUGGLING_HEALTH: f32 = 0.3;
+/// players doing better than this on all fronts get more pressure
+const DOMINATING_HEALTH: f32 = 0.7;
+const DOMINATING_KILL_RATE: f32 = 0.5;
+/// damage taken per second, relative to max life, considered as heavy
+const HEAVY_DAMAGE_RATE: f32 = 0.05;
+
+const RELIEF_DISTANCE: f32 = 80.;
+const RELIEF_PLACEMENT_ATTEMPTS: usize = 8;
+
+pub fn director_system(
+    mut commands: Commands,
+    mut levels: Query<(
+        Entity,
+        &mut Director,
+        &mut LevelInfo,
+        &mut EntropyComponent<WyRand>,
+    )>,
+    players: Query<(&Transform, &Life, &health::MaxLife), (With<Player>, With<Character>)>,
+    walls: Query<(&Transform, &levels::Wall)>,
+    mut deaths: EventReader<DeathEvent>,
+    mut writer: EventWriter<DirectorEvent>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_seconds();
+    let kill_count = deaths
+        .read()
+        .filter(|death| death.archetype.is_some() && death.killer.is_some())
+        .count();
+    let player_life = players.iter().map(|(_, life, _)| life.0).sum::<f32>();
+    let player_max_life = players
+        .iter()
+        .map(|(_, _, max_life)| max_life.0)
+        .sum::<f32>();
+    let weakest = players
+        .iter()
+        .map(|(transform, life, max_life)| (transform, life.0 / max_life.0))
+        .min_by(|(_, a), (_, b)| a.total_cmp(b));
+    let lowest_health = weakest.map_or(1., |(_, health)| health);
+
+    levels.for_each_mut(|(entity, mut director, mut level, mut entropy)| {
+        if let Some(last) = director.last_player_life {
+            if player_life < last {
+                director.damages.push_back((now, last - player_life));
+            }
+        }
+        director.last_player_life = Some(player_life);
+        director
+            .kills
+            .extend(std::iter::repeat(now).take(kill_count));
+
+        let window_start = now - director.settings.window;
+        while director
+            .damages
+            .front()
+            .is_some_and(|(t, _)| *t < window_start)
+        {
+            director.damages.pop_front();
+        }
+        while director.kills.front().is_some_and(|t| *t < window_start) {
+            director.kills.pop_front();
+        }
+
+        director.relief_timer.tick(time.delta());
+        if !director.decision_timer.tick(time.delta()).just_finished() {
+            return;
+        }
+
+        let damage_rate =
+            director.damages.iter().map(|(_, d)| *d).sum::<f32>() / director.settings.window;
+        let kill_rate = director.kills.len() as f32 / director.settings.window;
+        let heavy_damage = damage_rate > HEAVY_DAMAGE_RATE * player_max_life;
+
+        let mut decisions = vec![];
+        if lowest_health < STRUGGLING_HEALTH || heavy_damage {
+            let pressure = (director.pressure - director.settings.pressure_step)
+                .max(director.settings.min_pressure);
+            if pressure != director.pressure {
+                director.pressure = pressure;
+                decisions.push(DirectorDecision::LowerPressure(pressure));
+            }
+            if lowest_health < STRUGGLING_HEALTH && director.relief_timer.finished() {
+                if let Some((transform, _)) = weakest {
+                    let player_position = transform.translation.truncate();
+                    let in_wall = |point: Vec2| {
+                        walls.iter().any(|(wall_transform, wall)| {
+                            wall.penetration(
+                                wall_transform.translation.truncate(),
+                                point,
+                                Vec2::splat(pickups::HEALTH_PICKUP_RADIUS),
+                            )
+                            .is_some()
+                        })
+                    };
+                    // right under the player when nothing around is free
+                    let position = (0..RELIEF_PLACEMENT_ATTEMPTS)
+                        .map(|_| {
+                            let angle = entropy.gen_range(0. ..std::f32::consts::TAU);
+                            (player_position + Vec2::from_angle(angle) * RELIEF_DISTANCE)
+                                .clamp(level.arena.min, level.arena.max)
+                        })
+                        .find(|point| !in_wall(*point))
+                        .unwrap_or(player_position);
+                    pickups::spawn_health_pickup(
+                        &mut commands,
+                        position,
+                        director.settings.relief_heal,
+                    );
+                    director.relief_timer.reset();
+                    decisions.push(DirectorDecision::GrantRelief(position));
+                }
+            }
+        } else if lowest_health > DOMINATING_HEALTH && kill_rate > DOMINATING_KILL_RATE {
+            let pressure = (director.pressure + director.settings.pressure_step)
+                .min(director.settings.max_pressure);
+            if pressure != director.pressure {
+                director.pressure = pressure;
+                decisions.push(DirectorDecision::RaisePressure(pressure));
+            } else if director.extra_enemies < director.settings.max_extra_enemies {
+                // already at max pressure, put more enemies in
+                let extra_enemy = director.settings.extra_enemy.clone();
+                if let Some(desc) = level
+                    .enemy_to_spawn
+                    .iter_mut()
+                    .find(|desc| desc.enemy == extra_enemy && desc.class != EnemyClass::Boss)
+                {
+                    desc.amount += 1;
+                    director.extra_enemies += 1;
+                    decisions.push(DirectorDecision::InsertEnemies(1));
+                }
+            }
+        }
+
+        decisions.into_iter().for_each(|decision| {
+            writer.send(DirectorEvent {
+                level: entity,
+                decision,
+                damage_rate,
+                kill_rate,
+                lowest_health,
+            });
+        });
+    });
+}
+
+pub fn director_log_system(mut reader: EventReader<DirectorEvent>) {
+    reader.read().for_each(|event| {
+        info!("director: {:?}", event);
+    });
+}
@@ -0,0 +1,178 @@
+use crate::*;
+use enemies::{Archetype, ExplodeOnDeath, SpawnOnDeath};
+use enemy_targeting::HostileTarget;
+use health::{DamageDealt, MaxLife, Shield};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EliteAffix {
+    Fast,
+    Armored,
+    Vampiric,
+    Splitting,
+    Exploding,
+    Shielded,
+    Teleporting,
+}
+impl EliteAffix {
+    pub const ALL: [EliteAffix; 7] = [
+        EliteAffix::Fast,
+        EliteAffix::Armored,
+        EliteAffix::Vampiric,
+        EliteAffix::Splitting,
+        EliteAffix::Exploding,
+        EliteAffix::Shielded,
+        EliteAffix::Teleporting,
+    ];
+
+    pub fn tint(self) -> Color {
+        match self {
+            EliteAffix::Fast => Color::YELLOW,
+            EliteAffix::Armored => Color::GRAY,
+            EliteAffix::Vampiric => Color::PURPLE,
+            EliteAffix::Splitting => Color::LIME_GREEN,
+            EliteAffix::Exploding => Color::ORANGE,
+            EliteAffix::Shielded => Color::CYAN,
+            EliteAffix::Teleporting => Color::FUCHSIA,
+        }
+    }
+}
+
+const MAX_AFFIXES: usize = 2;
+pub fn roll_affixes(chance: f64, entropy: &mut EntropyComponent<WyRand>) -> Vec<EliteAffix> {
+    if !entropy.gen_bool(chance) {
+        return vec![];
+    }
+    let count = entropy.gen_range(1..=MAX_AFFIXES);
+    EliteAffix::ALL
+        .choose_multiple(entropy, count)
+        .copied()
+        .collect()
+}
+
+/// Affixes rolled for an enemy, they are applied after the enemy is initialized
+#[derive(Component)]
+pub struct EliteAffixes(pub Vec<EliteAffix>);
+#[derive(Component)]
+pub struct Elite;
+
+/// Part of incoming damage that is ignored, on top of flat [`health::Armor`]
+#[derive(Component)]
+pub struct Armored(pub f32);
+/// Part of dealt damage that heals the dealer, from bullets, contact and dashes alike
+#[derive(Component)]
+pub struct Vampiric(pub f32);
+/// Jumps next to its target every time the timer finishes
+#[derive(Component)]
+pub struct Teleporting(pub Timer);
+
+const FAST_SPEED_MULTIPLIER: f32 = 1.6;
+
+/// Layers the rolled affixes on top of whatever the type initializer built
+pub fn elite_initializer(
+    mut commands: Commands,
+    mut elites: Query<
+        (
+            Entity,
+            &EliteAffixes,
+            &Archetype,
+            &NormalClass,
+            &mut Sprite,
+            &mut movements::Movable,
+        ),
+        (With<Enemy>, Without<Elite>),
+    >,
+) {
+    elites.for_each_mut(
+        |(
+            entity,
+            EliteAffixes(affixes),
+            Archetype(archetype),
+            NormalClass(strength),
+            mut sprite,
+            mut movable,
+        )| {
+            let mut entity_commands = commands.entity(entity);
+            affixes.iter().for_each(|affix| match affix {
+                EliteAffix::Fast => movable.speed *= FAST_SPEED_MULTIPLIER,
+                EliteAffix::Armored => {
+                    entity_commands.insert(Armored(0.5));
+                }
+                EliteAffix::Vampiric => {
+                    entity_commands.insert(Vampiric(0.5));
+                }
+                // splits into weaker enemies of the same type
+                EliteAffix::Splitting => {
+                    entity_commands.insert(SpawnOnDeath {
+                        archetype: archetype.clone(),
+                        strength: strength.saturating_sub(1).max(1),
+                        count: 2,
+                        spread: 24.,
+                    });
+                }
+                EliteAffix::Exploding => {
+                    entity_commands.insert(ExplodeOnDeath {
+                        damage: 30.,
+                        friendly_fire: false,
+                    });
+                }
+                EliteAffix::Shielded => {
+                    entity_commands.insert(Shield::new(100., 20., 3.));
+                }
+                EliteAffix::Teleporting => {
+                    entity_commands
+                        .insert(Teleporting(Timer::from_seconds(4., TimerMode::Repeating)));
+                }
+            });
+
+            let tint = affixes
+                .iter()
+                .map(|affix| Vec4::from(affix.tint().as_rgba_f32()))
+                .sum::<Vec4>()
+                / affixes.len() as f32;
+            sprite.color = Color::rgb(tint.x, tint.y, tint.z);
+            entity_commands.insert(Elite);
+        },
+    );
+}
+
+/// Heals vampiric dealers by part of the life they took, up to their max life.
+/// Every damage source goes through [`DamageDealt`], contact damage included.
+pub fn vampiric_system(
+    mut reader: EventReader<DamageDealt>,
+    mut vampires: Query<(&Vampiric, &mut Life, Option<&MaxLife>)>,
+) {
+    reader.read().for_each(|dealt| {
+        let Some(Ok((Vampiric(ratio), mut life, max_life))) =
+            dealt.source.map(|source| vampires.get_mut(source))
+        else {
+            return;
+        };
+        life.0 = (life.0 + dealt.life_lost * ratio).min(max_life.map_or(f32::INFINITY, |m| m.0));
+    });
+}
+
+const TELEPORT_DISTANCE: f32 = 150.;
+pub fn teleporting_system(
+    mut teleporters: Query<(&mut Teleporting, &mut Transform, &HostileTarget, &LevelRef)>,
+    targets: Query<&Transform, Without<Teleporting>>,
+    mut levels: Query<(&LevelInfo, &mut EntropyComponent<WyRand>)>,
+    time: Res<Time>,
+) {
+    teleporters.for_each_mut(
+        |(mut teleporting, mut transform, HostileTarget(target), LevelRef(level))| {
+            if !teleporting.0.tick(time.delta()).just_finished() {
+                return;
+            }
+            let (Ok(target_transform), Ok((level, mut entropy))) =
+                (targets.get(*target), levels.get_mut(*level))
+            else {
+                return;
+            };
+            let angle = entropy.gen_range(0. ..std::f32::consts::TAU);
+            let point = (target_transform.translation.truncate()
+                + Vec2::from_angle(angle) * TELEPORT_DISTANCE)
+                .clamp(level.arena.min, level.arena.max);
+            transform.translation = point.extend(transform.translation.z);
+        },
+    );
+}
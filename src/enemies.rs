@@ -1,30 +1,372 @@
-use bevy::ecs::query::With;
+use std::fmt;
+
+use bevy::ecs::{query::With, system::EntityCommands};
 
 use crate::*;
 
-pub trait EnemyType: Component {
-    const TEXT: &'static str;
+/// A kind of enemy that can be spawned by its name.
+/// It only marks the spawned entity, the initializer of the type
+/// builds the rest once the entity has an [`InitPosition`].
+pub trait EnemyType: Send + Sync + 'static {
+    fn name(&self) -> &str;
+
+    fn insert(&self, enemy: &mut EntityCommands);
+}
+
+#[derive(Debug)]
+pub struct UnknownEnemyType(pub String);
+impl fmt::Display for UnknownEnemyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown enemy type `{}`", self.0)
+    }
+}
+impl std::error::Error for UnknownEnemyType {}
+
+/// Enemy types by name, register new ones with [`EnemyRegistryExt::register_enemy`]
+#[derive(Resource, Default)]
+pub struct EnemyRegistry(HashMap<String, Box<dyn EnemyType>>);
+impl EnemyRegistry {
+    pub fn register(&mut self, enemy_type: impl EnemyType) {
+        self.0
+            .insert(enemy_type.name().into(), Box::new(enemy_type));
+    }
+
+    pub fn get(&self, name: &str) -> Result<&dyn EnemyType, UnknownEnemyType> {
+        self.0
+            .get(name)
+            .map(|enemy_type| enemy_type.as_ref())
+            .ok_or_else(|| UnknownEnemyType(name.into()))
+    }
+}
+
+pub trait EnemyRegistryExt {
+    fn register_enemy(&mut self, enemy_type: impl EnemyType) -> &mut Self;
+
+    /// Registers an enemy type defined by a scene file, see [`prefab_enemy`]
+    fn register_enemy_prefab(&mut self, name: &str, path: &'static str) -> &mut Self;
+}
+impl EnemyRegistryExt for App {
+    fn register_enemy(&mut self, enemy_type: impl EnemyType) -> &mut Self {
+        self.init_resource::<EnemyRegistry>();
+        self.world
+            .resource_mut::<EnemyRegistry>()
+            .register(enemy_type);
+        self
+    }
+
+    fn register_enemy_prefab(&mut self, name: &str, path: &'static str) -> &mut Self {
+        let scene = self.world.resource::<AssetServer>().load(path);
+        self.register_enemy(prefab_enemy::PrefabEnemy {
+            name: name.into(),
+            scene,
+        })
+    }
+}
+
+/// Collider radius that can be authored in scene files
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct BallCollider(pub f32);
+pub fn ball_collider_system(
+    mut commands: Commands,
+    query: Query<(Entity, &BallCollider), Without<Collider>>,
+) {
+    query.for_each(|(entity, BallCollider(radius))| {
+        commands.entity(entity).insert(Collider::ball(*radius));
+    });
+}
+
+/// Name of the type the enemy was spawned as
+#[derive(Component, Reflect, Clone)]
+pub struct Archetype(pub String);
+
+/// Multipliers and rewards of an enemy strength tier,
+/// the tier is read from [`NormalClass`] when the enemy is initialized
+#[derive(Clone, Copy)]
+pub struct EnemyTier {
+    pub health: f32,
+    pub speed: f32,
+    pub damage: f32,
+    pub size: f32,
+    pub score: u32,
+    pub experience: u32,
+}
+/// the weakest tier, enemies keep what their initializer gave them
+impl Default for EnemyTier {
+    fn default() -> Self {
+        Self {
+            health: 1.,
+            speed: 1.,
+            damage: 1.,
+            size: 1.,
+            score: 10,
+            experience: 1,
+        }
+    }
+}
+
+/// Tier table indexed by strength, starting from 1
+#[derive(Resource)]
+pub struct EnemyTierTable(pub Vec<EnemyTier>);
+impl Default for EnemyTierTable {
+    fn default() -> Self {
+        Self(
+            (0..5)
+                .map(|i| {
+                    let i = i as f32;
+                    EnemyTier {
+                        health: 1. + 0.75 * i,
+                        speed: 1. + 0.1 * i,
+                        damage: 1. + 0.5 * i,
+                        size: 1. + 0.15 * i,
+                        score: 10 * (1 + i as u32),
+                        experience: 1 + 2 * i as u32,
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+impl EnemyTierTable {
+    /// strengths above the table share the strongest tier,
+    /// an empty table falls back to the default tier
+    pub fn get(&self, strength: u32) -> EnemyTier {
+        let index = strength.max(1) as usize - 1;
+        self.0
+            .get(index)
+            .or(self.0.last())
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Scales damage of bullets shot by the weapons of this character
+#[derive(Component, Reflect, Clone, Copy)]
+pub struct DamageMultiplier(pub f32);
 
-    fn to_component(self) -> impl Component;
+/// Granted to players when this enemy dies
+#[derive(Component, Reflect, Clone, Copy)]
+pub struct Reward {
+    pub score: u32,
+    pub experience: u32,
 }
 
-pub fn type_dispatch(str: &str) -> impl EnemyType {
-    match str {
-        normal_enemy::NormalEnemy::TEXT => normal_enemy::NormalEnemy,
-        _ => panic!("unknown enemy type"),
+#[derive(Component)]
+pub struct TierApplied;
+
+/// Applies the tier to enemies which are already initialized by their type initializer
+pub fn enemy_tier_system(
+    mut commands: Commands,
+    tiers: Res<EnemyTierTable>,
+    mut enemies: Query<
+        (
+            Entity,
+            &NormalClass,
+            &mut Life,
+            &mut movements::Movable,
+            &mut Transform,
+        ),
+        (With<Enemy>, Without<TierApplied>),
+    >,
+) {
+    enemies.for_each_mut(
+        |(entity, NormalClass(strength), mut life, mut movable, mut transform)| {
+            let tier = tiers.get(*strength);
+            life.0 *= tier.health;
+            movable.speed *= tier.speed;
+            transform.scale *= tier.size;
+            commands.entity(entity).insert((
+                DamageMultiplier(tier.damage),
+                health::MaxLife(life.0),
+                Reward {
+                    score: tier.score,
+                    experience: tier.experience,
+                },
+                TierApplied,
+            ));
+        },
+    );
+}
+
+/// Spawns `count` enemies of `archetype` around this enemy when it dies,
+/// they belong to the same level
+#[derive(Component, Clone)]
+pub struct SpawnOnDeath {
+    pub archetype: String,
+    pub strength: u32,
+    pub count: u32,
+    pub spread: f32,
+}
+
+/// Runs before dead entities are despawned.
+/// Spawns are telegraphed where the enemy died, not at a safe spawn point, splitting is the point.
+pub fn spawn_on_death_system(
+    mut commands: Commands,
+    dying: Query<(&Life, &Transform, &SpawnOnDeath, &LevelRef)>,
+    levels: Query<&LevelInfo>,
+) {
+    dying.for_each(|(life, transform, spawn_on_death, LevelRef(level))| {
+        let Ok(level_info) = levels.get(*level) else {
+            return;
+        };
+        if life.0 > 0. {
+            return;
+        }
+        (0..spawn_on_death.count).for_each(|i| {
+            let angle = std::f32::consts::TAU * i as f32 / spawn_on_death.count as f32;
+            let offset = Vec2::from_angle(angle) * spawn_on_death.spread;
+            levels::spawn_warning(
+                &mut commands,
+                transform.translation.truncate() + offset,
+                levels::SpawnWarning {
+                    timer: Timer::from_seconds(level_info.spawn_warning_time, TimerMode::Once),
+                    enemy: spawn_on_death.archetype.clone(),
+                    class: EnemyClass::Normal(spawn_on_death.strength),
+                    affixes: vec![],
+                },
+                *level,
+            );
+        });
+    });
+}
+
+/// Damage dealt to players touching this enemy
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct ContactDamage(pub f32);
+
+/// Detonates an explosion where this enemy dies
+#[derive(Component)]
+pub struct ExplodeOnDeath {
+    pub damage: f32,
+    /// whether the explosion hurts enemies as well
+    pub friendly_fire: bool,
+}
+
+/// Runs before dead entities are despawned
+pub fn explode_on_death_system(
+    dying: Query<(Entity, &Life, &Transform, &ExplodeOnDeath)>,
+    mut writer: EventWriter<bullets::BulletSpawnEvent>,
+) {
+    dying.for_each(|(entity, life, transform, explode)| {
+        if life.0 > 0. {
+            return;
+        }
+        let mut bullet_extras: Vec<Box<dyn BulletExtra>> = vec![];
+        if explode.friendly_fire {
+            bullet_extras.push(Box::new(bullets::friendly_fire::FriendlyFire));
+        }
+        writer.send(bullets::BulletSpawnEvent {
+            shooter: *transform,
+            by: entity,
+            // the explosion has no weapon, it outlives the enemy anyway
+            with: entity,
+            bullet: Bullet {
+                life_time: 0.3,
+                endurance: INFINITY,
+                hit_limit: 1.,
+                speed: 0.,
+                cooldown: 0.,
+                damage: explode.damage,
+            },
+            bullet_type: Box::new(bullets::explode_shot::ExplodeShot),
+            bullet_extras,
+            generation: 0,
+        });
+    });
+}
+
+/// Blocks bullets arriving within `arc` radians of either side of where it faces,
+/// hits from behind or the sides go through
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct FrontalShield {
+    pub arc: f32,
+    /// damage the shield takes before it breaks
+    pub durability: f32,
+    /// sends blocked bullets back instead of stopping them
+    pub reflect: bool,
+}
+impl FrontalShield {
+    /// whether a bullet at `from` hits the shield of a holder at `holder`
+    pub fn covers(&self, holder: &Transform, from: Vec3) -> bool {
+        if self.durability <= 0. {
+            return false;
+        }
+        // `aim_system` turns the holder's Y axis toward its aim
+        let facing = holder.rotation.mul_vec3(Vec3::Y).truncate();
+        let incoming = (from - holder.translation).truncate();
+        incoming != Vec2::ZERO && facing.angle_between(incoming).abs() <= self.arc
     }
 }
 
+/// Sprite of a [`FrontalShield`], goes away when the shield breaks
+#[derive(Component)]
+pub struct ShieldVisual;
+
+pub fn frontal_shield_break_system(
+    mut commands: Commands,
+    shields: Query<(Entity, &FrontalShield, Option<&Children>)>,
+    visuals: Query<(), With<ShieldVisual>>,
+) {
+    shields.for_each(|(entity, shield, children)| {
+        if shield.durability > 0. {
+            return;
+        }
+        commands.entity(entity).remove::<FrontalShield>();
+        children
+            .into_iter()
+            .flatten()
+            .filter(|child| visuals.contains(**child))
+            .for_each(|child| commands.entity(*child).despawn_recursive());
+    });
+}
+
+/// Turns reflected bullets around, they now belong to the shield holder
+pub fn shield_reflect_system(
+    mut commands: Commands,
+    mut reader: EventReader<BulletHitEvent>,
+    mut bullets: Query<&mut Transform, With<Bullet>>,
+) {
+    reader.read().for_each(|event| {
+        if event.kind != HitKind::Reflected || !event.first_hit {
+            return;
+        }
+        let Ok(mut transform) = bullets.get_mut(event.bullet_entity) else {
+            return;
+        };
+        // the hit count is kept, so the holder is not hit again on the way out
+        transform.rotate_z(std::f32::consts::PI);
+        commands
+            .entity(event.bullet_entity)
+            .remove::<Player>()
+            .insert((
+                Shooter(event.target),
+                // the holder is no weapon, so the bullet no longer chains the player's loads
+                WeaponRef(event.target),
+                movements::Movement::DirectionMove(transform.rotation.mul_vec3(Vec3::Y).truncate()),
+                CollisionLayers::new([Layer::EnemyBullet], [Layer::Player, Layer::PlayerBullet]),
+            ));
+    });
+}
+
 pub mod normal_enemy {
     use super::*;
 
     #[derive(Component)]
     pub struct NormalEnemy;
 
+    impl NormalEnemy {
+        pub const TEXT: &'static str = "normal";
+    }
+
     impl EnemyType for NormalEnemy {
-        const TEXT: &'static str = "normal";
-        fn to_component(self) -> impl Component {
-            self
+        fn name(&self) -> &str {
+            Self::TEXT
+        }
+
+        fn insert(&self, enemy: &mut EntityCommands) {
+            enemy.insert(NormalEnemy);
         }
     }
 
@@ -48,17 +390,662 @@ pub mod normal_enemy {
                         transform: initial_pos.0,
                         ..Default::default()
                     },
-                    Life(100),
+                    Life(100.),
+                    ContactDamage(10.),
                     Aims(Vec2::ZERO),
                     movements::Movable { speed: 150.0 },
                     Collider::ball(16.),
                     // Sensor,
-                    CollisionLayers::new([Layer::Enemy], [Layer::Player, Layer::PlayerBullet]),
+                    CollisionLayers::new(
+                        [Layer::Enemy],
+                        [
+                            Layer::Player,
+                            Layer::PlayerBullet,
+                            Layer::Wall,
+                            Layer::Blast,
+                        ],
+                    ),
+                    AimTargetingType::AimCurrent,
+                    MoveTargetingType::Chase,
+                ))
+                .remove::<InitPosition>();
+        });
+    }
+}
+
+/// Keeps its distance from the target and shoots at it
+pub mod ranged_enemy {
+    use super::*;
+
+    #[derive(Component)]
+    pub struct RangedEnemy;
+
+    impl RangedEnemy {
+        pub const TEXT: &'static str = "ranged";
+    }
+
+    impl EnemyType for RangedEnemy {
+        fn name(&self) -> &str {
+            Self::TEXT
+        }
+
+        fn insert(&self, enemy: &mut EntityCommands) {
+            enemy.insert(RangedEnemy);
+        }
+    }
+
+    pub fn ranged_enemy_initializer(
+        mut commands: Commands,
+        to_initialize: Query<(Entity, &InitPosition), (With<Character>, With<RangedEnemy>)>,
+    ) {
+        to_initialize.for_each(|(entity, initial_pos)| {
+            commands
+                .entity(entity)
+                .insert((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: Color::TEAL,
+                            rect: Some(Rect {
+                                min: Vec2::new(0.0, 0.0),
+                                max: Vec2::new(24.0, 24.0),
+                            }),
+                            ..Default::default()
+                        },
+                        transform: initial_pos.0,
+                        ..Default::default()
+                    },
+                    Life(60.),
+                    ContactDamage(5.),
+                    Aims(Vec2::ZERO),
+                    movements::Movable { speed: 120.0 },
+                    Collider::ball(12.),
+                    CollisionLayers::new(
+                        [Layer::Enemy],
+                        [
+                            Layer::Player,
+                            Layer::PlayerBullet,
+                            Layer::Wall,
+                            Layer::Blast,
+                        ],
+                    ),
+                    AimTargetingType::AimPredict,
+                    enemy_targeting::AimError::new(0.08),
+                    MoveTargetingType::Kite {
+                        near: 180.,
+                        far: 280.,
+                    },
+                    enemy_targeting::FireRange(320.),
+                    // spreads out so shots come from many sides
+                    steering::Steering {
+                        separation_radius: 80.,
+                        ..Default::default()
+                    },
+                ))
+                .with_children(|cb| {
+                    cb.spawn(Weapon {
+                        accelerate: 100.,
+                        loads: vec![BulletLoader {
+                            bullet: Bullet {
+                                life_time: 2.,
+                                endurance: 1.,
+                                hit_limit: 1.,
+                                speed: 300.,
+                                cooldown: 1.5,
+                                damage: 8.,
+                            },
+                            bullet_type: Box::new(bullets::lane_shot::LaneShot),
+                            bullet_extras: vec![],
+                        }],
+                    });
+                })
+                .remove::<InitPosition>();
+        });
+    }
+}
+
+/// Stops, winds up showing where it is going and dashes at the target
+pub mod charger_enemy {
+    use bevy::sprite::Anchor;
+
+    use super::*;
+    use enemy_targeting::HostileTarget;
+    use forced_moving::{Dashing, ForcedMove};
+
+    #[derive(Component)]
+    pub struct ChargerEnemy;
+
+    impl ChargerEnemy {
+        pub const TEXT: &'static str = "charger";
+    }
+
+    impl EnemyType for ChargerEnemy {
+        fn name(&self) -> &str {
+            Self::TEXT
+        }
+
+        fn insert(&self, enemy: &mut EntityCommands) {
+            enemy.insert(ChargerEnemy);
+        }
+    }
+
+    #[derive(Component)]
+    pub struct Charger {
+        /// starts winding up when the target gets this close
+        pub trigger_distance: f32,
+        pub windup: f32,
+        pub dash_speed: f32,
+        pub dash_time: f32,
+        pub recover: f32,
+        /// recover time after running into a wall
+        pub stun: f32,
+        pub dash_damage: f32,
+    }
+
+    #[derive(Component)]
+    pub enum ChargerState {
+        Idle,
+        Windup {
+            timer: Timer,
+            direction: Vec2,
+        },
+        Dash {
+            timer: Timer,
+            direction: Vec2,
+            hit: bool,
+        },
+        Recover {
+            timer: Timer,
+        },
+    }
+
+    /// Shows the path of the coming dash
+    #[derive(Component)]
+    pub struct DashTelegraph;
+
+    pub fn charger_enemy_initializer(
+        mut commands: Commands,
+        to_initialize: Query<(Entity, &InitPosition), (With<Character>, With<ChargerEnemy>)>,
+    ) {
+        to_initialize.for_each(|(entity, initial_pos)| {
+            let charger = Charger {
+                trigger_distance: 250.,
+                windup: 0.8,
+                dash_speed: 700.,
+                dash_time: 0.5,
+                recover: 0.8,
+                stun: 2.,
+                dash_damage: 25.,
+            };
+            let telegraph_length = charger.dash_speed * charger.dash_time;
+            commands
+                .entity(entity)
+                .insert((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: Color::MAROON,
+                            rect: Some(Rect {
+                                min: Vec2::new(0.0, 0.0),
+                                max: Vec2::new(36.0, 36.0),
+                            }),
+                            ..Default::default()
+                        },
+                        transform: initial_pos.0,
+                        ..Default::default()
+                    },
+                    Life(150.),
+                    ContactDamage(10.),
+                    Aims(Vec2::ZERO),
+                    movements::Movable { speed: 100.0 },
+                    Collider::ball(18.),
+                    CollisionLayers::new(
+                        [Layer::Enemy],
+                        [
+                            Layer::Player,
+                            Layer::PlayerBullet,
+                            Layer::Wall,
+                            Layer::Blast,
+                        ],
+                    ),
+                    AimTargetingType::AimCurrent,
+                    MoveTargetingType::Chase,
+                    charger,
+                    ChargerState::Idle,
+                ))
+                .with_children(|cb| {
+                    cb.spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color: Color::rgba(1.0, 0.0, 0.0, 0.3),
+                                custom_size: Some(Vec2::new(8., telegraph_length)),
+                                anchor: Anchor::BottomCenter,
+                                ..Default::default()
+                            },
+                            visibility: Visibility::Hidden,
+                            ..Default::default()
+                        },
+                        DashTelegraph,
+                    ));
+                })
+                .remove::<InitPosition>();
+        });
+    }
+
+    /// Drives chargers through idle, windup, dash and recover
+    pub fn charger_system(
+        mut commands: Commands,
+        mut chargers: Query<(
+            Entity,
+            &Charger,
+            &mut ChargerState,
+            &Transform,
+            &mut Aims,
+            &CollidingEntities,
+            &ColliderAabb,
+            &Children,
+            Option<&HostileTarget>,
+            Option<&DamageMultiplier>,
+        )>,
+        targets: Query<&Transform, With<Life>>,
+        walls: Query<(&Transform, &levels::Wall)>,
+        // dashing through an invulnerable player doesn't hurt it
+        players: Query<(), (With<Player>, With<Character>, Without<Invulnerable>)>,
+        mut writer: EventWriter<health::DamageEvent>,
+        mut telegraphs: Query<&mut Visibility, With<DashTelegraph>>,
+        time: Res<Time>,
+    ) {
+        chargers.for_each_mut(
+            |(
+                entity,
+                charger,
+                mut state,
+                transform,
+                mut aims,
+                colliding_entities,
+                aabb,
+                children,
+                target,
+                damage_multiplier,
+            )| {
+                let position = transform.translation.truncate();
+                let mut show_telegraph = |visibility: Visibility| {
+                    let mut telegraphs = telegraphs.iter_many_mut(children);
+                    while let Some(mut telegraph) = telegraphs.fetch_next() {
+                        *telegraph = visibility;
+                    }
+                };
+                let next = match &mut *state {
+                    ChargerState::Idle => {
+                        let Some(target_position) = target
+                            .and_then(|HostileTarget(target)| targets.get(*target).ok())
+                            .map(|target_transform| target_transform.translation.truncate())
+                        else {
+                            return;
+                        };
+                        if position.distance(target_position) > charger.trigger_distance {
+                            return;
+                        }
+                        // stand still and lock the aim while winding up
+                        commands
+                            .entity(entity)
+                            .remove::<(MoveTargetingType, AimTargetingType, movements::Movement)>()
+                            .insert(LinearVelocity(Vec2::ZERO));
+                        aims.0 = target_position;
+                        show_telegraph(Visibility::Inherited);
+                        ChargerState::Windup {
+                            timer: Timer::from_seconds(charger.windup, TimerMode::Once),
+                            direction: (target_position - position).normalize_or_zero(),
+                        }
+                    }
+                    ChargerState::Windup { timer, direction } => {
+                        if !timer.tick(time.delta()).just_finished() {
+                            return;
+                        }
+                        commands.entity(entity).insert((
+                            ForcedMove {
+                                direction: *direction,
+                                speed: charger.dash_speed,
+                            },
+                            Dashing,
+                        ));
+                        show_telegraph(Visibility::Hidden);
+                        ChargerState::Dash {
+                            timer: Timer::from_seconds(charger.dash_time, TimerMode::Once),
+                            direction: *direction,
+                            hit: false,
+                        }
+                    }
+                    ChargerState::Dash {
+                        timer,
+                        direction,
+                        hit,
+                    } => {
+                        if !*hit {
+                            if let Some(player) =
+                                colliding_entities.iter().find(|e| players.contains(**e))
+                            {
+                                let multiplier = damage_multiplier.map_or(1., |m| m.0);
+                                writer.send(health::DamageEvent {
+                                    target: *player,
+                                    source: Some(entity),
+                                    amount: charger.dash_damage * multiplier,
+                                });
+                                *hit = true;
+                            }
+                        }
+                        // walls leave chargers touching them, only the ones dashed into count
+                        let half_extents = aabb.half_extents();
+                        let half_size = Vec2::new(half_extents.x, half_extents.y);
+                        let hit_wall = colliding_entities.iter().any(|e| {
+                            walls.get(*e).is_ok_and(|(wall_transform, wall)| {
+                                wall.penetration(
+                                    wall_transform.translation.truncate(),
+                                    position,
+                                    half_size,
+                                )
+                                .is_some_and(|(normal, _)| normal.dot(*direction) < 0.)
+                            })
+                        });
+                        if !hit_wall && !timer.tick(time.delta()).just_finished() {
+                            return;
+                        }
+                        commands.entity(entity).remove::<(ForcedMove, Dashing)>();
+                        // running into a wall stuns the charger for longer
+                        let recover = if hit_wall {
+                            charger.stun
+                        } else {
+                            charger.recover
+                        };
+                        ChargerState::Recover {
+                            timer: Timer::from_seconds(recover, TimerMode::Once),
+                        }
+                    }
+                    ChargerState::Recover { timer } => {
+                        if !timer.tick(time.delta()).just_finished() {
+                            return;
+                        }
+                        commands
+                            .entity(entity)
+                            .insert((MoveTargetingType::Chase, AimTargetingType::AimCurrent));
+                        ChargerState::Idle
+                    }
+                };
+                *state = next;
+            },
+        );
+    }
+}
+
+/// Walks up behind a frontal shield, it has to be flanked
+pub mod shield_enemy {
+    use super::*;
+
+    #[derive(Component)]
+    pub struct ShieldEnemy;
+
+    impl ShieldEnemy {
+        pub const TEXT: &'static str = "shield";
+    }
+
+    impl EnemyType for ShieldEnemy {
+        fn name(&self) -> &str {
+            Self::TEXT
+        }
+
+        fn insert(&self, enemy: &mut EntityCommands) {
+            enemy.insert(ShieldEnemy);
+        }
+    }
+
+    pub fn shield_enemy_initializer(
+        mut commands: Commands,
+        to_initialize: Query<(Entity, &InitPosition), (With<Character>, With<ShieldEnemy>)>,
+    ) {
+        to_initialize.for_each(|(entity, initial_pos)| {
+            commands
+                .entity(entity)
+                .insert((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: Color::OLIVE,
+                            rect: Some(Rect {
+                                min: Vec2::new(0.0, 0.0),
+                                max: Vec2::new(28.0, 28.0),
+                            }),
+                            ..Default::default()
+                        },
+                        transform: initial_pos.0,
+                        ..Default::default()
+                    },
+                    Life(80.),
+                    ContactDamage(15.),
+                    Aims(Vec2::ZERO),
+                    movements::Movable { speed: 90.0 },
+                    Collider::ball(14.),
+                    CollisionLayers::new(
+                        [Layer::Enemy],
+                        [
+                            Layer::Player,
+                            Layer::PlayerBullet,
+                            Layer::Wall,
+                            Layer::Blast,
+                        ],
+                    ),
+                    AimTargetingType::AimCurrent,
+                    MoveTargetingType::Chase,
+                    FrontalShield {
+                        arc: std::f32::consts::FRAC_PI_3,
+                        durability: 150.,
+                        reflect: false,
+                    },
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color: Color::SILVER,
+                                custom_size: Some(Vec2::new(30., 6.)),
+                                ..Default::default()
+                            },
+                            transform: Transform::from_xyz(0., 18., 0.1),
+                            ..Default::default()
+                        },
+                        ShieldVisual,
+                    ));
+                })
+                .remove::<InitPosition>();
+        });
+    }
+}
+
+/// Rushes at the target and blows up next to it, or wherever it is killed
+pub mod kamikaze_enemy {
+    use super::*;
+    use enemy_targeting::HostileTarget;
+
+    #[derive(Component)]
+    pub struct KamikazeEnemy;
+
+    impl KamikazeEnemy {
+        pub const TEXT: &'static str = "kamikaze";
+    }
+
+    impl EnemyType for KamikazeEnemy {
+        fn name(&self) -> &str {
+            Self::TEXT
+        }
+
+        fn insert(&self, enemy: &mut EntityCommands) {
+            enemy.insert(KamikazeEnemy);
+        }
+    }
+
+    /// Lit when the target gets within `trigger_distance`,
+    /// the enemy detonates when the timer finishes
+    #[derive(Component)]
+    pub struct Fuse {
+        pub trigger_distance: f32,
+        pub timer: Timer,
+        pub lit: bool,
+    }
+
+    pub fn kamikaze_enemy_initializer(
+        mut commands: Commands,
+        to_initialize: Query<(Entity, &InitPosition), (With<Character>, With<KamikazeEnemy>)>,
+    ) {
+        to_initialize.for_each(|(entity, initial_pos)| {
+            commands
+                .entity(entity)
+                .insert((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: Color::GOLD,
+                            rect: Some(Rect {
+                                min: Vec2::new(0.0, 0.0),
+                                max: Vec2::new(20.0, 20.0),
+                            }),
+                            ..Default::default()
+                        },
+                        transform: initial_pos.0,
+                        ..Default::default()
+                    },
+                    Life(40.),
+                    ContactDamage(5.),
+                    Aims(Vec2::ZERO),
+                    movements::Movable { speed: 230.0 },
+                    Collider::ball(10.),
+                    CollisionLayers::new(
+                        [Layer::Enemy],
+                        [
+                            Layer::Player,
+                            Layer::PlayerBullet,
+                            Layer::Wall,
+                            Layer::Blast,
+                        ],
+                    ),
                     AimTargetingType::AimCurrent,
                     MoveTargetingType::Chase,
+                    // packs tight so the blast catches more
+                    steering::Steering {
+                        separation: 0.5,
+                        ..Default::default()
+                    },
+                    Fuse {
+                        trigger_distance: 60.,
+                        timer: Timer::from_seconds(0.6, TimerMode::Once),
+                        lit: false,
+                    },
+                    ExplodeOnDeath {
+                        damage: 40.,
+                        friendly_fire: true,
+                    },
                 ))
                 .remove::<InitPosition>();
         });
     }
+
+    const FUSE_BLINK_PER_SECOND: f32 = 12.;
+    pub fn fuse_system(
+        mut commands: Commands,
+        mut fuses: Query<(
+            Entity,
+            &mut Fuse,
+            &mut Life,
+            &mut Visibility,
+            &Transform,
+            Option<&HostileTarget>,
+        )>,
+        targets: Query<&Transform, Without<Fuse>>,
+        time: Res<Time>,
+    ) {
+        fuses.for_each_mut(
+            |(entity, mut fuse, mut life, mut visibility, transform, target)| {
+                if !fuse.lit {
+                    fuse.lit = target
+                        .and_then(|HostileTarget(target)| targets.get(*target).ok())
+                        .is_some_and(|target_transform| {
+                            transform.translation.distance(target_transform.translation)
+                                <= fuse.trigger_distance
+                        });
+                    if fuse.lit {
+                        commands
+                            .entity(entity)
+                            .remove::<(MoveTargetingType, movements::Movement)>()
+                            .insert(LinearVelocity(Vec2::ZERO));
+                    }
+                    return;
+                }
+                if fuse.timer.tick(time.delta()).just_finished() {
+                    life.0 = 0.;
+                } else if (fuse.timer.elapsed_secs() * FUSE_BLINK_PER_SECOND) as u32 % 2 == 0 {
+                    *visibility = Visibility::Inherited;
+                } else {
+                    *visibility = Visibility::Hidden;
+                }
+            },
+        );
+    }
 }
 
+/// Enemies defined by scene files such as `assets/entities/base_enemy.scn.ron`.
+/// Components of the first entity in the scene are copied onto the enemy,
+/// so they have to be registered with `#[reflect(Component)]`.
+pub mod prefab_enemy {
+    use bevy::ecs::reflect::ReflectCommandExt;
+
+    use super::*;
+
+    pub struct PrefabEnemy {
+        pub name: String,
+        pub scene: Handle<DynamicScene>,
+    }
+
+    impl EnemyType for PrefabEnemy {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn insert(&self, enemy: &mut EntityCommands) {
+            enemy.insert(Prefab(self.scene.clone()));
+        }
+    }
+
+    #[derive(Component)]
+    pub struct Prefab(pub Handle<DynamicScene>);
+
+    pub fn prefab_enemy_initializer(
+        mut commands: Commands,
+        to_initialize: Query<(Entity, &InitPosition, &Prefab), With<Character>>,
+        scenes: Res<Assets<DynamicScene>>,
+    ) {
+        to_initialize.for_each(|(entity, initial_pos, Prefab(scene))| {
+            // wait until the scene is loaded
+            let Some(scene) = scenes.get(scene) else {
+                return;
+            };
+            let Some(prefab) = scene.entities.first() else {
+                warn!("enemy prefab scene has no entity");
+                commands.entity(entity).despawn();
+                return;
+            };
+            let mut entity_commands = commands.entity(entity);
+            entity_commands.insert(SpriteBundle::default());
+            prefab.components.iter().for_each(|component| {
+                entity_commands.insert_reflect(component.clone_value());
+            });
+            entity_commands
+                .insert((
+                    initial_pos.0,
+                    CollisionLayers::new(
+                        [Layer::Enemy],
+                        [
+                            Layer::Player,
+                            Layer::PlayerBullet,
+                            Layer::Wall,
+                            Layer::Blast,
+                        ],
+                    ),
+                ))
+                .remove::<InitPosition>();
+        });
+    }
+}
@@ -1,111 +1,606 @@
 use crate::*;
 // use bevy::prelude::*;
 
-pub fn enemy_search_nearist_player(
-    player: Query<(Entity, &Transform, &LinearVelocity), (With<Player>, With<Life>)>,
-    wait_target: Query<
+/// Threat each possible target built up on an enemy, the target is re-evaluated periodically
+#[derive(Component)]
+pub struct Aggro {
+    threat: HashMap<Entity, f32>,
+    timer: Timer,
+}
+impl Aggro {
+    fn new(entity: Entity) -> Self {
+        let mut timer = Timer::from_seconds(AGGRO_REEVALUATE_SECONDS, TimerMode::Repeating);
+        // spread re-evaluations over frames
+        timer.set_elapsed(Duration::from_secs_f32(
+            (entity.index() % 10) as f32 / 10. * AGGRO_REEVALUATE_SECONDS,
+        ));
+        Self {
+            threat: HashMap::new(),
+            timer,
+        }
+    }
+
+    pub fn add(&mut self, source: Entity, threat: f32) {
+        *self.threat.entry(source).or_default() += threat;
+    }
+}
+
+/// Draws the attention of enemies within `radius`, building `threat` per second on them
+#[derive(Component)]
+pub struct Taunt {
+    pub radius: f32,
+    pub threat: f32,
+}
+
+const AGGRO_REEVALUATE_SECONDS: f32 = 1.;
+/// part of the threat lost per second
+const THREAT_DECAY: f32 = 0.2;
+/// threat of a target right next to the enemy, fading out to nothing at `AGGRO_RANGE`
+const PROXIMITY_THREAT: f32 = 50.;
+const AGGRO_RANGE: f32 = 600.;
+/// a new target has to beat the current one by this factor
+const SWITCH_MARGIN: f32 = 1.2;
+
+/// Damage that got through armor turns into threat toward its source
+pub fn damage_threat_system(
+    mut reader: EventReader<health::DamageDealt>,
+    mut aggros: Query<&mut Aggro>,
+) {
+    reader.read().for_each(|dealt| {
+        let (Some(source), Ok(mut aggro)) = (dealt.source, aggros.get_mut(dealt.target)) else {
+            return;
+        };
+        aggro.add(source, dealt.taken());
+    });
+}
+
+pub fn enemy_aggro_system(
+    candidates: Query<(Entity, &Transform, Option<&Taunt>), (With<Player>, With<Life>)>,
+    mut enemies: Query<
         (
             Entity,
             &Transform,
-            Option<&MoveTargetingType>,
-            Option<&AimTargetingType>,
+            Option<&mut Aggro>,
+            Option<&HostileTarget>,
+            Has<MoveTargetingType>,
+            Has<AimTargetingType>,
         ),
-        (With<Enemy>, Without<HostileTarget>),
+        With<Enemy>,
     >,
     mut commands: Commands,
+    time: Res<Time>,
 ) {
-    wait_target.for_each(|(entity, transform, move_targeting, aim_targeting)| {
-        let Some(nearist) = player.iter().min_by_key(|(_, player_transform, _)| {
-            transform.translation.distance(player_transform.translation) as i32
-        }) else {
-            return;
-        };
-        if move_targeting.is_some() || aim_targeting.is_some() {
-            commands.entity(entity).insert(HostileTarget(nearist.0));
-        }
-    });
+    enemies.for_each_mut(
+        |(entity, transform, aggro, current, move_targeting, aim_targeting)| {
+            if !move_targeting && !aim_targeting {
+                return;
+            }
+            let Some(mut aggro) = aggro else {
+                commands.entity(entity).insert(Aggro::new(entity));
+                return;
+            };
+
+            let decay = (1. - THREAT_DECAY * time.delta_seconds()).max(0.);
+            aggro
+                .threat
+                .values_mut()
+                .for_each(|threat| *threat *= decay);
+            candidates.for_each(|(candidate, candidate_transform, taunt)| {
+                let distance = transform
+                    .translation
+                    .distance(candidate_transform.translation);
+                if let Some(taunt) = taunt.filter(|taunt| distance <= taunt.radius) {
+                    aggro.add(candidate, taunt.threat * time.delta_seconds());
+                }
+            });
+
+            let current = current
+                .map(|HostileTarget(target)| *target)
+                .filter(|target| candidates.contains(*target));
+            if !aggro.timer.tick(time.delta()).just_finished() && current.is_some() {
+                return;
+            }
+            aggro
+                .threat
+                .retain(|source, _| candidates.contains(*source));
+
+            let score = |candidate: Entity, candidate_transform: &Transform| {
+                let distance = transform
+                    .translation
+                    .distance(candidate_transform.translation);
+                aggro.threat.get(&candidate).copied().unwrap_or_default()
+                    + PROXIMITY_THREAT * (1. - distance / AGGRO_RANGE).max(0.)
+            };
+            let Some((best, best_score)) = candidates
+                .iter()
+                .map(|(candidate, candidate_transform, _)| {
+                    (candidate, score(candidate, candidate_transform))
+                })
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            else {
+                return;
+            };
+            let keep_current = current.is_some_and(|current| {
+                current == best
+                    || candidates
+                        .get(current)
+                        .is_ok_and(|(_, current_transform, _)| {
+                            score(current, current_transform) * SWITCH_MARGIN >= best_score
+                        })
+            });
+            if !keep_current {
+                commands.entity(entity).insert(HostileTarget(best));
+            }
+        },
+    );
 }
 
 #[derive(Component)]
-pub struct HostileTarget(Entity);
+pub struct HostileTarget(pub Entity);
 
-#[derive(Component)]
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
 pub(crate) enum MoveTargetingType {
+    #[default]
     Chase,
     Follow,
-    Outflank,
+    /// Takes a slot on a ring of `radius` around the target, then closes in from there
+    Outflank {
+        radius: f32,
+    },
+    /// Keeps the target between `near` and `far`, strafing around it in between
+    Kite {
+        near: f32,
+        far: f32,
+    },
+    /// Moves straight away from the target, sliding along the arena edge once it gets there
+    Retreat,
+    /// Circles around the target at whatever distance it is
+    Strafe,
+}
+
+/// half of the strafers go clockwise, others go counterclockwise
+fn strafe_direction(entity: Entity, away: Vec2) -> Vec2 {
+    if entity.index() % 2 == 0 {
+        away.perp()
+    } else {
+        -away.perp()
+    }
+}
+
+/// distance from the arena edge where retreating enemies stop moving outward
+const ARENA_LEASH_MARGIN: f32 = 32.;
+
+/// Drops the part of `direction` that would take `position` out of `arena`
+fn leash_to_arena(direction: Vec2, position: Vec2, arena: Rect) -> Vec2 {
+    let min = arena.min + ARENA_LEASH_MARGIN;
+    let max = arena.max - ARENA_LEASH_MARGIN;
+    let leash = |value: f32, min: f32, max: f32, direction: f32| {
+        if (value <= min && direction < 0.) || (value >= max && direction > 0.) {
+            0.
+        } else {
+            direction
+        }
+    };
+    Vec2::new(
+        leash(position.x, min.x, max.x, direction.x),
+        leash(position.y, min.y, max.y, direction.y),
+    )
+    .normalize_or_zero()
+}
+
+/// Angle around `target` an outflanking enemy comes from
+#[derive(Component)]
+pub struct FlankSlot {
+    pub target: Entity,
+    pub angle: f32,
+}
+
+/// an enemy this close to its slot angle stops circling and closes in
+const FLANK_ANGLE_TOLERANCE: f32 = 0.35;
+/// slots drift a bit every frame as the group moves, smaller shifts are not worth an update
+const FLANK_SLOT_SHIFT: f32 = 0.05;
+
+fn angle_around(center: Vec2, point: Vec2) -> f32 {
+    let offset = point - center;
+    offset.y.atan2(offset.x)
+}
+
+/// Spreads enemies outflanking the same target evenly around it.
+/// Slots keep the order enemies already have around the target so nobody crosses over.
+pub fn flank_slot_system(
+    mut commands: Commands,
+    flankers: Query<(
+        Entity,
+        &MoveTargetingType,
+        &Transform,
+        Option<&HostileTarget>,
+        Option<&FlankSlot>,
+    )>,
+    targets: Query<&Transform, With<Life>>,
+) {
+    let mut groups: HashMap<Entity, Vec<(Entity, f32, Option<&FlankSlot>)>> = HashMap::new();
+    flankers.for_each(|(entity, targeting_type, transform, target, slot)| {
+        let (MoveTargetingType::Outflank { .. }, Some((target, target_transform))) = (
+            targeting_type,
+            target.and_then(|HostileTarget(target)| Some((*target, targets.get(*target).ok()?))),
+        ) else {
+            // not outflanking anything anymore
+            if slot.is_some() {
+                commands.entity(entity).remove::<FlankSlot>();
+            }
+            return;
+        };
+        let angle = angle_around(
+            target_transform.translation.truncate(),
+            transform.translation.truncate(),
+        );
+        groups
+            .entry(target)
+            .or_default()
+            .push((entity, angle, slot));
+    });
+
+    groups.into_iter().for_each(|(target, mut group)| {
+        group.sort_by(|(_, a, _), (_, b, _)| a.total_cmp(b));
+        let step = std::f32::consts::TAU / group.len() as f32;
+        // rotates the evenly spaced slots to fit where enemies already are
+        let base = group
+            .iter()
+            .enumerate()
+            .map(|(i, (_, angle, _))| Vec2::from_angle(angle - i as f32 * step))
+            .sum::<Vec2>();
+        let base = base.y.atan2(base.x);
+        group.iter().enumerate().for_each(|(i, (entity, _, slot))| {
+            let angle = base + i as f32 * step;
+            let unchanged = slot.is_some_and(|slot| {
+                slot.target == target
+                    && Vec2::from_angle(slot.angle)
+                        .angle_between(Vec2::from_angle(angle))
+                        .abs()
+                        < FLANK_SLOT_SHIFT
+            });
+            if !unchanged {
+                commands.entity(*entity).insert(FlankSlot { target, angle });
+            }
+        });
+    });
 }
 
 pub fn move_targeting_system(
     mut commands: Commands,
-    mut query: Query<(Entity, &MoveTargetingType, &Transform, &HostileTarget)>,
+    mut query: Query<(
+        Entity,
+        &MoveTargetingType,
+        &Transform,
+        &HostileTarget,
+        Option<&line_of_sight::LineOfSight>,
+        Option<&FlankSlot>,
+    )>,
     living_entities: Query<(Entity, &Transform, &LinearVelocity), (With<Life>)>,
+    level_refs: Query<&LevelRef>,
+    levels: Query<&LevelInfo>,
+    navigation: Res<navigation::Navigation>,
 ) {
     query.for_each_mut(
-        |(entity, targeting_type, transform, HostileTarget(target_entity))| {
+        |(entity, targeting_type, transform, HostileTarget(target_entity), sight, slot)| {
             let Some(target) = living_entities.get(*target_entity).ok() else {
                 commands.entity(entity).remove::<HostileTarget>();
                 return;
             };
+            // goes around walls on the way to `goal`
+            let route = |goal: Vec2| {
+                navigation.route(transform.translation.truncate(), goal, *target_entity)
+            };
+            // lost sight of the target, search where it was last seen
+            if let Some(point) = sight
+                .filter(|sight| !sight.visible)
+                .and_then(|sight| sight.last_known)
+            {
+                commands
+                    .entity(entity)
+                    .insert(movements::Movement::PointMove(
+                        navigation.search(transform.translation.truncate(), point),
+                    ));
+                return;
+            }
             match targeting_type {
                 MoveTargetingType::Chase => {
                     let (_, player_transform, player_speed) = target;
                     commands
                         .entity(entity)
-                        .insert(movements::Movement::PointMove(
+                        .insert(movements::Movement::PointMove(route(
                             player_transform.translation.truncate(),
-                        ));
+                        )));
                 }
                 MoveTargetingType::Follow => {
                     let (_, player_transform, player_speed) = target;
                     if transform.translation.distance(player_transform.translation) > 100. {
                         commands
                             .entity(entity)
-                            .insert(movements::Movement::PointMove(
+                            .insert(movements::Movement::PointMove(route(
                                 player_transform.translation.truncate(),
-                            ));
+                            )));
                     } else {
                         commands.entity(entity).remove::<movements::Movement>();
                     }
                 }
-                MoveTargetingType::Outflank => {
-                    let (_, player_transform, player_speed) = target;
-                    let target =
-                        player_transform.translation.truncate() + player_speed.0.normalize() * 1.5;
+                MoveTargetingType::Outflank { radius } => {
+                    let (_, player_transform, _) = target;
+                    let target_position = player_transform.translation.truncate();
+                    let position = transform.translation.truncate();
+                    let goal = match slot {
+                        Some(slot)
+                            if slot.target == *target_entity
+                                && Vec2::from_angle(slot.angle)
+                                    .angle_between(position - target_position)
+                                    .abs()
+                                    > FLANK_ANGLE_TOLERANCE =>
+                        {
+                            target_position + Vec2::from_angle(slot.angle) * *radius
+                        }
+                        _ => target_position,
+                    };
                     commands
                         .entity(entity)
-                        .insert(movements::Movement::PointMove(target));
+                        .insert(movements::Movement::PointMove(route(goal)));
+                }
+                MoveTargetingType::Kite { near, far } => {
+                    let (_, player_transform, _) = target;
+                    let offset = (transform.translation - player_transform.translation).truncate();
+                    let away = offset.normalize_or_zero();
+                    let direction = match offset.length() {
+                        d if d < *near => away,
+                        d if d > *far => -away,
+                        _ => strafe_direction(entity, away),
+                    };
+                    commands
+                        .entity(entity)
+                        .insert(movements::Movement::DirectionMove(direction));
+                }
+                MoveTargetingType::Retreat | MoveTargetingType::Strafe => {
+                    let (_, player_transform, _) = target;
+                    let away = (transform.translation - player_transform.translation)
+                        .truncate()
+                        .normalize_or_zero();
+                    let direction = if let MoveTargetingType::Retreat = targeting_type {
+                        away
+                    } else {
+                        strafe_direction(entity, away)
+                    };
+                    let arena = level_refs
+                        .get(entity)
+                        .and_then(|LevelRef(level)| levels.get(*level))
+                        .map(|level| level.arena)
+                        .ok();
+                    let position = transform.translation.truncate();
+                    let direction = match arena {
+                        Some(arena) => {
+                            let leashed = leash_to_arena(direction, position, arena);
+                            // cornered, slides along the edge instead
+                            if leashed == Vec2::ZERO {
+                                leash_to_arena(strafe_direction(entity, away), position, arena)
+                            } else {
+                                leashed
+                            }
+                        }
+                        None => direction,
+                    };
+                    commands
+                        .entity(entity)
+                        .insert(movements::Movement::DirectionMove(direction));
                 }
             }
         },
     );
 }
 
+/// Weapons of an enemy only shoot while its target is within this distance
 #[derive(Component)]
-pub struct AimTarget(Entity);
+pub struct FireRange(pub f32);
+
+pub fn fire_control_system(
+    mut commands: Commands,
+    shooters: Query<
+        (
+            &Transform,
+            &FireRange,
+            &Children,
+            Option<&HostileTarget>,
+            Has<behaviors::HoldFire>,
+            Option<&line_of_sight::LineOfSight>,
+        ),
+        With<Enemy>,
+    >,
+    targets: Query<&Transform, With<Life>>,
+    weapons: Query<Entity, With<Weapon>>,
+) {
+    shooters.for_each(
+        |(transform, FireRange(range), children, target, hold_fire, sight)| {
+            let in_range = !hold_fire
+                && sight.map_or(true, |sight| sight.visible)
+                && target
+                    .and_then(|HostileTarget(target)| targets.get(*target).ok())
+                    .is_some_and(|target_transform| {
+                        transform.translation.distance(target_transform.translation) <= *range
+                    });
+            weapons.iter_many(children).for_each(|weapon| {
+                if in_range {
+                    commands.entity(weapon).insert(IsShooting);
+                } else {
+                    commands.entity(weapon).remove::<IsShooting>();
+                }
+            });
+        },
+    );
+}
+
 #[derive(Component)]
+pub struct AimTarget(Entity);
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
 pub(crate) enum AimTargetingType {
+    #[default]
     AimCurrent,
     AimPredict,
 }
+/// Misses by up to `spread` radians on either side, the miss is rolled again every so often
+#[derive(Component)]
+pub struct AimError {
+    spread: f32,
+    offset: f32,
+    timer: Timer,
+}
+impl AimError {
+    /// a negative spread aims true
+    pub fn new(spread: f32) -> Self {
+        Self {
+            spread: spread.max(0.),
+            offset: 0.,
+            timer: Timer::from_seconds(AIM_ERROR_RESAMPLE_SECONDS, TimerMode::Repeating),
+        }
+    }
+}
+const AIM_ERROR_RESAMPLE_SECONDS: f32 = 0.4;
+
+/// Time for a bullet of `speed` to meet a target at `offset` moving at `velocity`
+fn intercept_time(offset: Vec2, velocity: Vec2, speed: f32) -> Option<f32> {
+    let a = velocity.length_squared() - speed * speed;
+    let b = 2. * offset.dot(velocity);
+    let c = offset.length_squared();
+    if a.abs() < f32::EPSILON {
+        return (b < 0.).then(|| -c / b);
+    }
+    let discriminant = b * b - 4. * a * c;
+    if discriminant < 0. {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    [(-b - root) / (2. * a), (-b + root) / (2. * a)]
+        .into_iter()
+        .filter(|t| *t > 0.)
+        .reduce(f32::min)
+}
+
 pub fn aim_targeting_system(
-    mut query: Query<(&AimTargetingType, &mut Aims, &HostileTarget), With<Enemy>>,
+    mut query: Query<
+        (
+            &AimTargetingType,
+            &mut Aims,
+            &Transform,
+            &HostileTarget,
+            Option<&Children>,
+            Option<&line_of_sight::LineOfSight>,
+            Option<&mut AimError>,
+            Option<&LevelRef>,
+        ),
+        With<Enemy>,
+    >,
     player: Query<(&Transform, &LinearVelocity), (With<Life>)>,
+    weapons: Query<&Weapon>,
+    mut levels: Query<&mut EntropyComponent<WyRand>>,
+    time: Res<Time>,
 ) {
-    query.for_each_mut(|(targeting_type, mut aims, HostileTarget(target_entity))| {
-        let Some(target) = player.get(*target_entity).ok() else {
-            return;
-        };
-        match targeting_type {
-            AimTargetingType::AimCurrent => {
-                let (player_transform, player_speed) = target;
-                aims.0 = player_transform.translation.truncate();
+    query.for_each_mut(
+        |(
+            targeting_type,
+            mut aims,
+            transform,
+            HostileTarget(target_entity),
+            children,
+            sight,
+            aim_error,
+            level,
+        )| {
+            // keeps aiming where it was until the target shows up again
+            if sight.is_some_and(|sight| !sight.visible) {
+                return;
             }
-            AimTargetingType::AimPredict => {
-                let (player_transform, player_speed) = target;
-                aims.0 = player_transform.translation.truncate() + player_speed.0 * 0.5;
+            let Some((player_transform, player_speed)) = player.get(*target_entity).ok() else {
+                return;
+            };
+            let position = transform.translation.truncate();
+            let target = player_transform.translation.truncate();
+            let aim = match targeting_type {
+                AimTargetingType::AimCurrent => target,
+                AimTargetingType::AimPredict => {
+                    let bullet_speed = weapons
+                        .iter_many(children.into_iter().flatten())
+                        .find_map(|weapon| weapon.loads.first())
+                        .map(|loader| loader.bullet.speed);
+                    bullet_speed
+                        .and_then(|speed| intercept_time(target - position, player_speed.0, speed))
+                        .map_or(target, |t| target + player_speed.0 * t)
+                }
+            };
+
+            let Some(mut aim_error) = aim_error else {
+                aims.0 = aim;
+                return;
+            };
+            if aim_error.timer.tick(time.delta()).just_finished() {
+                if let Some(mut entropy) =
+                    level.and_then(|LevelRef(level)| levels.get_mut(*level).ok())
+                {
+                    let spread = aim_error.spread;
+                    aim_error.offset = entropy.gen_range(-spread..=spread);
+                }
             }
-        }
-    });
+            aims.0 = position + Vec2::from_angle(aim_error.offset).rotate(aim - position);
+        },
+    );
 }
 
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::TAU;
+
+    use super::*;
+
+    fn spawn_flanker(app: &mut App, target: Entity, position: Vec2) -> Entity {
+        app.world
+            .spawn((
+                MoveTargetingType::Outflank { radius: 100. },
+                Transform::from_translation(position.extend(0.)),
+                HostileTarget(target),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn flankers_spread_evenly_around_their_target() {
+        let mut app = App::new();
+        app.add_systems(Update, flank_slot_system);
+        let target = app.world.spawn((Transform::default(), Life(100.))).id();
+        let flankers = [Vec2::new(50., 0.), Vec2::new(0., 50.), Vec2::new(-50., 5.)]
+            .map(|position| spawn_flanker(&mut app, target, position));
+        app.update();
+
+        let angles = flankers.map(|flanker| {
+            let slot = app.world.get::<FlankSlot>(flanker).unwrap();
+            assert_eq!(slot.target, target);
+            slot.angle
+        });
+        (0..angles.len()).for_each(|i| {
+            let gap = Vec2::from_angle(angles[i])
+                .angle_between(Vec2::from_angle(angles[(i + 1) % angles.len()]));
+            assert!((gap.abs() - TAU / 3.).abs() < 1e-3);
+        });
+    }
+
+    #[test]
+    fn slot_is_dropped_once_not_outflanking() {
+        let mut app = App::new();
+        app.add_systems(Update, flank_slot_system);
+        let target = app.world.spawn((Transform::default(), Life(100.))).id();
+        let flanker = spawn_flanker(&mut app, target, Vec2::new(50., 0.));
+        app.update();
+        assert!(app.world.get::<FlankSlot>(flanker).is_some());
+
+        app.world
+            .entity_mut(flanker)
+            .insert(MoveTargetingType::Chase);
+        app.update();
+        assert!(app.world.get::<FlankSlot>(flanker).is_none());
+    }
+}
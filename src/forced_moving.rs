@@ -1,4 +1,9 @@
 use bevy::{math::vec3, prelude::*};
+
+use crate::{
+    movements::{XAxisMove, YAxisMove},
+    Aims, Invulnerable,
+};
 #[derive(Component)]
 pub(crate) struct ForcedMove {
     pub(crate) direction: Vec2,
@@ -15,6 +20,10 @@ pub(crate) fn forced_move_system(
     });
 }
 
+/// Marks a character moving by its own [`ForcedMove`], shocks won't override it
+#[derive(Component)]
+pub(crate) struct Dashing;
+
 #[derive(Component)]
 pub(crate) struct Shocked {
     pub(crate) impact: f32,
@@ -60,3 +69,122 @@ pub(crate) fn shock_timer_system(
     });
 }
 
+/// Dash of the player, each dash uses a charge and charges come back one at a time
+#[derive(Component)]
+pub(crate) struct DashAbility {
+    pub(crate) charges: u32,
+    pub(crate) max_charges: u32,
+    pub(crate) recharge: Timer,
+    pub(crate) speed: f32,
+    pub(crate) duration: f32,
+    /// seconds bullets and contact can't hurt, counted from the start of the dash
+    pub(crate) invulnerability: f32,
+}
+impl Default for DashAbility {
+    fn default() -> Self {
+        Self {
+            charges: 2,
+            max_charges: 2,
+            recharge: Timer::from_seconds(1.5, TimerMode::Repeating),
+            speed: 900.,
+            duration: 0.15,
+            invulnerability: 0.25,
+        }
+    }
+}
+
+/// Set by input, consumed by [`dash_system`]
+#[derive(Component)]
+pub(crate) struct DashRequested;
+
+#[derive(Component)]
+pub(crate) struct DashTimer(Timer);
+
+pub(crate) fn dash_system(
+    mut commands: Commands,
+    mut dashers: Query<
+        (
+            Entity,
+            &mut DashAbility,
+            &Transform,
+            Option<&XAxisMove>,
+            Option<&YAxisMove>,
+            Option<&Aims>,
+            Has<Shocked>,
+        ),
+        (With<DashRequested>, Without<Dashing>),
+    >,
+    invulnerables: Query<&Invulnerable>,
+) {
+    dashers.for_each_mut(|(entity, mut dash, transform, x, y, aims, shocked)| {
+        commands.entity(entity).remove::<DashRequested>();
+        // no dashing out of a knockback
+        if dash.charges == 0 || shocked {
+            return;
+        }
+        let mut direction = Vec2::ZERO;
+        match x {
+            Some(XAxisMove::Left) => direction.x -= 1.,
+            Some(XAxisMove::Right) => direction.x += 1.,
+            None => {}
+        }
+        match y {
+            Some(YAxisMove::Up) => direction.y += 1.,
+            Some(YAxisMove::Down) => direction.y -= 1.,
+            None => {}
+        }
+        // standing still dashes toward the aim
+        if direction == Vec2::ZERO {
+            direction = aims.map_or(Vec2::ZERO, |aims| aims.0 - transform.translation.truncate());
+        }
+        let Some(direction) = direction.try_normalize() else {
+            return;
+        };
+
+        dash.charges -= 1;
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.insert((
+            ForcedMove {
+                direction,
+                speed: dash.speed,
+            },
+            Dashing,
+            DashTimer(Timer::from_seconds(dash.duration, TimerMode::Once)),
+        ));
+        // a longer invulnerability, from being hurt for instance, is kept
+        if invulnerables
+            .get(entity)
+            .map_or(true, |Invulnerable(timer)| {
+                timer.remaining_secs() < dash.invulnerability
+            })
+        {
+            entity_commands.insert(Invulnerable(Timer::from_seconds(
+                dash.invulnerability,
+                TimerMode::Once,
+            )));
+        }
+    });
+}
+
+pub(crate) fn dash_timer_system(
+    mut commands: Commands,
+    mut dashers: Query<(Entity, &mut DashAbility, Option<&mut DashTimer>)>,
+    time: Res<Time>,
+) {
+    dashers.for_each_mut(|(entity, mut dash, timer)| {
+        if let Some(mut timer) = timer {
+            if timer.0.tick(time.delta()).just_finished() {
+                commands
+                    .entity(entity)
+                    .remove::<(ForcedMove, Dashing, DashTimer)>();
+            }
+        }
+        if dash.charges < dash.max_charges {
+            if dash.recharge.tick(time.delta()).just_finished() {
+                dash.charges += 1;
+            }
+        } else {
+            dash.recharge.reset();
+        }
+    });
+}
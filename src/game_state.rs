@@ -0,0 +1,221 @@
+use crate::*;
+
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameState {
+    #[default]
+    MainMenu,
+    InRun,
+    Paused,
+    /// between two levels, the next level is already spawned
+    LevelTransition,
+    /// picking an upgrade after a level up, gameplay is frozen
+    Draft,
+    GameOver,
+}
+
+/// Systems that only run while a run is being played
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GameplaySet;
+
+/// Level the current run is at, levels count from 1
+#[derive(Resource, Default)]
+pub struct RunProgress {
+    pub level: i32,
+}
+
+#[derive(Resource)]
+pub struct TransitionTimer(Timer);
+impl Default for TransitionTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(TRANSITION_SECONDS, TimerMode::Once))
+    }
+}
+const TRANSITION_SECONDS: f32 = 2.;
+
+/// Text shown for the current state, despawned when leaving it
+#[derive(Component)]
+pub struct StateUi;
+
+pub fn state_input_system(
+    input: Res<Input<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    match state.get() {
+        GameState::MainMenu | GameState::GameOver if input.just_pressed(KeyCode::Return) => {
+            next_state.set(GameState::LevelTransition);
+        }
+        GameState::InRun if input.just_pressed(KeyCode::Escape) => {
+            next_state.set(GameState::Paused);
+        }
+        GameState::Paused if input.just_pressed(KeyCode::Escape) => {
+            next_state.set(GameState::InRun);
+        }
+        _ => {}
+    }
+}
+
+pub fn spawn_state_text(commands: &mut Commands, text: String) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            StateUi,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                text,
+                TextStyle {
+                    font_size: 40.,
+                    color: Color::WHITE,
+                    ..Default::default()
+                },
+            ));
+        });
+}
+
+pub fn main_menu_ui(mut commands: Commands) {
+    spawn_state_text(&mut commands, "press Enter to start".into());
+}
+
+pub fn paused_ui(mut commands: Commands) {
+    spawn_state_text(&mut commands, "paused".into());
+}
+
+pub fn game_over_ui(mut commands: Commands, score: Res<Score>, progress: Res<RunProgress>) {
+    spawn_state_text(
+        &mut commands,
+        format!(
+            "game over at level {}, score {}\npress Enter to retry",
+            progress.level, score.0
+        ),
+    );
+}
+
+pub fn level_transition_ui(mut commands: Commands, progress: Res<RunProgress>) {
+    spawn_state_text(&mut commands, format!("level {}", progress.level));
+}
+
+pub fn despawn_state_ui(mut commands: Commands, ui: Query<Entity, With<StateUi>>) {
+    ui.for_each(|entity| commands.entity(entity).despawn_recursive());
+}
+
+pub fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+pub fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+/// Clears everything left by the previous run before a new one starts
+pub fn reset_run(
+    mut commands: Commands,
+    run_entities: Query<
+        Entity,
+        (
+            Without<Parent>,
+            Or<(
+                With<Character>,
+                With<Bullet>,
+                With<LevelInfo>,
+                With<Wall>,
+                With<SpawnWarning>,
+                With<pickups::HealthPickup>,
+                With<pickups::ExperienceOrb>,
+                With<bosses::BossHealthBar>,
+            )>,
+        ),
+    >,
+    mut score: ResMut<Score>,
+    mut progress: ResMut<RunProgress>,
+) {
+    run_entities.for_each(|entity| commands.entity(entity).despawn_recursive());
+    *score = Score::default();
+    *progress = RunProgress::default();
+}
+
+/// Removes the finished level and moves on to the next one, players stay
+pub fn clear_level(
+    mut commands: Commands,
+    level_entities: Query<
+        Entity,
+        (
+            Without<Parent>,
+            Or<(
+                With<LevelInfo>,
+                With<Wall>,
+                With<SpawnWarning>,
+                With<Bullet>,
+                With<pickups::HealthPickup>,
+            )>,
+        ),
+    >,
+    orbs: Query<(Entity, &Transform, &pickups::ExperienceOrb)>,
+    mut players: Query<(&Transform, &mut upgrades::Experience), (With<Player>, With<Character>)>,
+    mut progress: ResMut<RunProgress>,
+    mut timer: ResMut<TransitionTimer>,
+) {
+    level_entities.for_each(|entity| commands.entity(entity).despawn_recursive());
+    // orbs left behind are not lost, they go to the nearest player
+    orbs.for_each(|(entity, transform, pickups::ExperienceOrb(experience))| {
+        let nearest = players
+            .iter_mut()
+            .map(|(player, player_experience)| {
+                let distance = player.translation.distance_squared(transform.translation);
+                (player_experience, distance)
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+        if let Some((mut player_experience, _)) = nearest {
+            player_experience.gain(*experience);
+        }
+        commands.entity(entity).despawn();
+    });
+    progress.level += 1;
+    timer.0.reset();
+}
+
+pub fn transition_timer_system(
+    mut timer: ResMut<TransitionTimer>,
+    mut next_state: ResMut<NextState<GameState>>,
+    time: Res<Time>,
+) {
+    if timer.0.tick(time.delta()).just_finished() {
+        next_state.set(GameState::InRun);
+    }
+}
+
+/// A level is cleared once it has nothing left to spawn and all of its enemies are dead
+pub fn level_cleared_system(
+    levels: Query<(Entity, &LevelInfo)>,
+    level_enemies: Query<&LevelRef, Or<(With<Enemy>, With<SpawnWarning>)>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let cleared = !levels.is_empty()
+        && levels.iter().all(|(entity, level)| {
+            level.enemy_to_spawn.iter().all(|desc| desc.amount == 0)
+                && !level_enemies
+                    .iter()
+                    .any(|LevelRef(enemy_level)| *enemy_level == entity)
+        });
+    if cleared {
+        next_state.set(GameState::LevelTransition);
+    }
+}
+
+pub fn game_over_system(
+    players: Query<(), (With<Player>, With<Character>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if players.is_empty() {
+        next_state.set(GameState::GameOver);
+    }
+}
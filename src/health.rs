@@ -0,0 +1,157 @@
+use bevy::utils::HashSet;
+
+use crate::*;
+
+/// Life a character starts with and can be healed back to
+#[derive(Component, Reflect, Default, Clone, Copy)]
+#[reflect(Component)]
+pub struct MaxLife(pub f32);
+
+/// Flat amount taken off every hit before it reaches shield or life
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+pub struct Armor(pub f32);
+
+/// Hit points lost before life, refilled after `regen_delay` seconds without being hit
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Shield {
+    pub amount: f32,
+    pub max: f32,
+    /// points regained per second
+    pub regen: f32,
+    pub regen_delay: f32,
+    #[reflect(ignore)]
+    since_hit: f32,
+}
+impl Shield {
+    pub fn new(max: f32, regen: f32, regen_delay: f32) -> Self {
+        Self {
+            amount: max,
+            max,
+            regen,
+            regen_delay,
+            since_hit: 0.,
+        }
+    }
+}
+impl Default for Shield {
+    fn default() -> Self {
+        Self::new(0., 0., 0.)
+    }
+}
+
+/// armor never takes off more than this part of a hit
+const ARMOR_MAX_REDUCTION: f32 = 0.8;
+
+/// Raw damage sent to `target`, it goes through [`damage_system`] before touching life
+#[derive(Event, Debug)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub source: Option<Entity>,
+    pub amount: f32,
+}
+
+/// What a [`DamageEvent`] ended up doing, `raw = armor_blocked + shield_absorbed + life_lost`
+#[derive(Event, Debug)]
+pub struct DamageDealt {
+    pub target: Entity,
+    pub source: Option<Entity>,
+    pub raw: f32,
+    pub armor_blocked: f32,
+    pub shield_absorbed: f32,
+    pub life_lost: f32,
+}
+impl DamageDealt {
+    /// damage that got through armor
+    pub fn taken(&self) -> f32 {
+        self.shield_absorbed + self.life_lost
+    }
+}
+
+/// Characters without a [`MaxLife`] get the life they spawned with,
+/// enemies get theirs from their tier or their initializer
+pub fn max_life_system(
+    mut commands: Commands,
+    lives: Query<(Entity, &Life), (Without<MaxLife>, Without<Enemy>)>,
+) {
+    lives.for_each(|(entity, life)| {
+        commands.entity(entity).insert(MaxLife(life.0));
+    });
+}
+
+/// Invulnerability, then flat armor, then elite armor, then shield, then life
+pub fn damage_system(
+    mut commands: Commands,
+    mut reader: EventReader<DamageEvent>,
+    mut targets: Query<(
+        &mut Life,
+        Option<&Armor>,
+        Option<&elites::Armored>,
+        Option<&mut Shield>,
+        Has<Invulnerable>,
+        Option<&HurtCooldown>,
+    )>,
+    mut writer: EventWriter<DamageDealt>,
+) {
+    // `Invulnerable` is only inserted once commands apply,
+    // later hits of this frame on a hurt target must not land either
+    let mut hurt = HashSet::new();
+    reader.read().for_each(|event| {
+        let Ok((mut life, armor, armored, shield, invulnerable, hurt_cooldown)) =
+            targets.get_mut(event.target)
+        else {
+            return;
+        };
+        if invulnerable || hurt.contains(&event.target) || event.amount <= 0. {
+            return;
+        }
+        let flat_blocked = armor.map_or(0., |Armor(armor)| {
+            armor.clamp(0., event.amount * ARMOR_MAX_REDUCTION)
+        });
+        let through = (event.amount - flat_blocked)
+            * (1. - armored.map_or(0., |elites::Armored(reduction)| *reduction));
+        let armor_blocked = event.amount - through;
+        let shield_absorbed = shield.map_or(0., |mut shield| {
+            shield.since_hit = 0.;
+            let absorbed = through.min(shield.amount);
+            shield.amount -= absorbed;
+            absorbed
+        });
+        let life_lost = through - shield_absorbed;
+        life.0 -= life_lost;
+
+        let mut entity_commands = commands.entity(event.target);
+        if let Some(source) = event.source {
+            entity_commands.insert(LastHitBy(source));
+        }
+        if let Some(HurtCooldown(seconds)) = hurt_cooldown.filter(|_| life_lost > 0.) {
+            entity_commands.insert(Invulnerable(Timer::from_seconds(*seconds, TimerMode::Once)));
+            hurt.insert(event.target);
+        }
+        writer.send(DamageDealt {
+            target: event.target,
+            source: event.source,
+            raw: event.amount,
+            armor_blocked,
+            shield_absorbed,
+            life_lost,
+        });
+    });
+}
+
+pub fn shield_regen_system(mut shields: Query<&mut Shield>, time: Res<Time>) {
+    shields.for_each_mut(|mut shield| {
+        shield.since_hit += time.delta_seconds();
+        if shield.since_hit < shield.regen_delay || shield.amount >= shield.max {
+            return;
+        }
+        shield.amount = (shield.amount + shield.regen * time.delta_seconds()).min(shield.max);
+    });
+}
+
+pub fn damage_log_system(mut reader: EventReader<DamageDealt>) {
+    reader.read().for_each(|event| {
+        debug!("damage: {:?}", event);
+    });
+}
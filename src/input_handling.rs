@@ -18,6 +18,12 @@ pub(crate) fn handle_input(
     input: Res<Input<KeyCode>>,
 ) {
     if let Some(player) = player.iter().next() {
+        if input.just_pressed(KeyCode::Space) {
+            commands
+                .entity(player)
+                .insert(super::forced_moving::DashRequested);
+        }
+
         if input.any_just_pressed([KeyCode::A, KeyCode::Left]) {
             commands.entity(player).insert(XAxisMove::Left);
         }
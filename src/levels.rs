@@ -1,6 +1,7 @@
 use super::*;
 use bevy::prelude::*;
-use enemies::EnemyType;
+use bevy::time::Stopwatch;
+use enemies::{EnemyRegistry, UnknownEnemyType};
 use std::{
     hash::{Hash, Hasher},
     time::Duration,
@@ -12,6 +13,15 @@ pub struct LevelInfo {
     pub is_spawning: bool,
     pub enemy_to_spawn: Vec<EnemyDescriptor>,
     pub wave_enemy_limit: usize,
+    /// enemies are only placed inside this area
+    pub arena: Rect,
+    /// enemies will not be placed closer than this to any player
+    pub spawn_safe_distance: f32,
+    /// seconds a spawn warning is shown before the enemy appears
+    pub spawn_warning_time: f32,
+    pub pacing: SpawnPacing,
+    /// chance of a spawned normal enemy to roll elite affixes
+    pub elite_chance: f64,
 }
 
 #[derive(Component)]
@@ -23,7 +33,7 @@ pub struct NextSpawnTimer(pub Timer);
 #[derive(Component)]
 pub struct SpawnedCounter(HashMap<EnemyDescriptor, u32>);
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy)]
 pub enum EnemyClass {
     /// Normal enemies will be spawned with pace according to existing enemies
     /// the parameter implies its strength
@@ -34,7 +44,7 @@ pub enum EnemyClass {
 #[derive(Component)]
 pub struct BossClass;
 #[derive(Component)]
-pub struct NormalClass(u32);
+pub struct NormalClass(pub u32);
 
 pub struct EnemyDescriptor {
     pub enemy: String,
@@ -47,110 +57,479 @@ impl Hash for EnemyDescriptor {
     }
 }
 #[derive(Component)]
-pub struct LevelRef(Entity);
+pub struct LevelRef(pub Entity);
+
+/// Piecewise linear curve through `(x, y)` keypoints sorted by `x`,
+/// it is clamped to the first and last keypoint outside of them.
+/// Two keypoints on the same `x` make a step.
+#[derive(Clone)]
+pub struct PacingCurve(pub Vec<(f32, f32)>);
+impl PacingCurve {
+    pub fn constant(y: f32) -> Self {
+        Self(vec![(0., y)])
+    }
+
+    pub fn sample(&self, x: f32) -> f32 {
+        let (Some(first), Some(last)) = (self.0.first(), self.0.last()) else {
+            return 1.;
+        };
+        if x <= first.0 {
+            return first.1;
+        }
+        self.0
+            .windows(2)
+            .find(|w| x <= w[1].0)
+            .map(|w| {
+                let t = (x - w[0].0) / (w[1].0 - w[0].0);
+                w[0].1 + (w[1].1 - w[0].1) * t
+            })
+            .unwrap_or(last.1)
+    }
+}
+
+/// How fast a level spawns its normal enemies
+pub struct SpawnPacing {
+    /// base spawn interval in seconds over level progress,
+    /// progress 1 equals [`LEVEL_TIME_BASE`] seconds since the level started
+    pub interval: PacingCurve,
+    /// interval multiplier over alive enemies divided by `wave_enemy_limit`
+    pub crowd: PacingCurve,
+    /// interval multiplier over the health ratio of the weakest player
+    pub player_health: PacingCurve,
+    /// enemies spawned at once over level progress, rounded
+    pub batch: PacingCurve,
+}
+impl Default for SpawnPacing {
+    fn default() -> Self {
+        Self {
+            interval: PacingCurve::constant(0.6),
+            crowd: PacingCurve(vec![(1., 1.), (1., 3.3), (2., 3.3), (2., 10.)]),
+            player_health: PacingCurve::constant(1.),
+            batch: PacingCurve::constant(1.),
+        }
+    }
+}
+
+#[derive(Component, Default)]
+pub struct LevelTime(pub Stopwatch);
 
 const LEVEL_TIME_BASE: f32 = 60.;
+const MIN_SPAWN_INTERVAL: f32 = 0.1;
 pub fn level_enemy_spawner(
     mut commands: Commands,
     mut levels: Query<(
         Entity,
         &mut LevelInfo,
         &mut NextSpawnTimer,
+        &mut LevelTime,
         &mut EntropyComponent<WyRand>,
+        Option<&director::Director>,
     )>,
-    level_enemies: Query<(&LevelRef), With<Enemy>>,
+    level_enemies: Query<(&LevelRef), Or<(With<Enemy>, With<SpawnWarning>)>>,
+    players: Query<(&Transform, &Life, &health::MaxLife), (With<Player>, With<Character>)>,
+    registry: Res<EnemyRegistry>,
+    spatial_query: SpatialQuery,
     time: Res<Time>,
 ) {
-    levels.for_each_mut(|(entity, mut level, mut spawn_timer, mut entropy)| {
-        // FIXME: remove this check, is_spawning shuould be a component
-        if !level.is_spawning {
-            return;
-        }
-        if spawn_timer.0.tick(time.delta()).just_finished() {
-            // let remains = counter.0.iter().map(|e| e.1).sum::<u32>() as i64;
-            let remains = level
+    let player_positions = players
+        .iter()
+        .map(|(transform, _, _)| transform.translation.truncate())
+        .collect::<Vec<_>>();
+    let lowest_player_health = players
+        .iter()
+        .map(|(_, life, max_life)| life.0 / max_life.0)
+        .fold(1., f32::min);
+    levels.for_each_mut(
+        |(entity, mut level, mut spawn_timer, mut level_time, mut entropy, director)| {
+            // FIXME: remove this check, is_spawning shuould be a component
+            if !level.is_spawning {
+                return;
+            }
+            // unknown types would only fail once their warning is over, drop them up front
+            if level
                 .enemy_to_spawn
                 .iter()
-                .filter(|e| e.class != EnemyClass::Boss)
-                .map(|e| e.amount)
-                .sum::<u32>() as i64;
-            if remains == 0 {
-                return;
+                .any(|desc| registry.get(&desc.enemy).is_err())
+            {
+                level
+                    .enemy_to_spawn
+                    .retain(|desc| match registry.get(&desc.enemy) {
+                        Ok(_) => true,
+                        Err(err) => {
+                            warn!("{err}, {} spawns are dropped", desc.amount);
+                            false
+                        }
+                    });
             }
+            let progress = level_time.0.tick(time.delta()).elapsed_secs() / LEVEL_TIME_BASE;
 
-            let mut next = entropy.gen_range(0..remains);
-            for desc in level.enemy_to_spawn.iter_mut() {
-                next -= desc.amount as i64;
-                if next < 0 {
-                    desc.amount -= 1;
-
-                    let EnemyClass::Normal(class) = desc.class else {
-                        unreachable!()
+            if spawn_timer.0.tick(time.delta()).just_finished() {
+                let batch = level.pacing.batch.sample(progress).round().max(1.) as usize;
+                for _ in 0..batch {
+                    // let remains = counter.0.iter().map(|e| e.1).sum::<u32>() as i64;
+                    let remains = level
+                        .enemy_to_spawn
+                        .iter()
+                        .filter(|e| e.class != EnemyClass::Boss)
+                        .map(|e| e.amount)
+                        .sum::<u32>() as i64;
+                    if remains == 0 {
+                        break;
+                    }
+                    let Some(spawn_point) =
+                        pick_spawn_point(&level, &mut entropy, &player_positions, &spatial_query)
+                    else {
+                        // no safe place this time, the rest of the batch waits for the next spawn
+                        break;
                     };
-                    // TODO: scene range
-                    let rand_transform = Transform::from_xyz(
-                        entropy.gen_range(-300. ..=300.),
-                        entropy.gen_range(-300. ..=300.),
-                        0.,
-                    );
-
-                    commands.spawn((
-                        enemies::type_dispatch(&desc.enemy).to_component(),
-                        NormalClass(class),
-                        Enemy,
-                        Character,
-                        InitPosition(rand_transform),
-                        LevelRef(entity),
-                    ));
-                    break;
+
+                    let warning_time = level.spawn_warning_time;
+                    let elite_chance = level.elite_chance;
+                    let mut next = entropy.gen_range(0..remains);
+                    for desc in level
+                        .enemy_to_spawn
+                        .iter_mut()
+                        .filter(|e| e.class != EnemyClass::Boss)
+                    {
+                        next -= desc.amount as i64;
+                        if next < 0 {
+                            desc.amount -= 1;
+
+                            spawn_warning(
+                                &mut commands,
+                                spawn_point,
+                                SpawnWarning {
+                                    timer: Timer::from_seconds(warning_time, TimerMode::Once),
+                                    enemy: desc.enemy.clone(),
+                                    class: desc.class,
+                                    affixes: elites::roll_affixes(elite_chance, &mut entropy),
+                                },
+                                entity,
+                            );
+                            break;
+                        }
+                    }
                 }
             }
-        }
 
-        let level_enemy_count = level_enemies
-            .iter()
-            .filter(|LevelRef(level_et)| *level_et == entity)
-            .count();
-        match level_enemy_count {
-            c if c < level.wave_enemy_limit => {
-                if spawn_timer.0.duration() != Duration::from_secs_f32(0.6) {
-                    spawn_timer.0.reset();
-                    spawn_timer.0.set_duration(Duration::from_secs_f32(0.6));
-                }
+            let level_enemy_count = level_enemies
+                .iter()
+                .filter(|LevelRef(level_et)| *level_et == entity)
+                .count();
+            let crowd = level_enemy_count as f32 / level.wave_enemy_limit.max(1) as f32;
+            let interval = level.pacing.interval.sample(progress)
+                * level.pacing.crowd.sample(crowd)
+                * level.pacing.player_health.sample(lowest_player_health)
+                / director.map_or(1., |director| director.pressure);
+            let interval = Duration::from_secs_f32(interval.max(MIN_SPAWN_INTERVAL));
+            if spawn_timer.0.duration() != interval {
+                spawn_timer.0.set_duration(interval);
             }
-            c if c >= level.wave_enemy_limit && c < 2 * level.wave_enemy_limit => {
-                if spawn_timer.0.duration() != Duration::from_secs_f32(2.) {
-                    spawn_timer.0.reset();
-                    spawn_timer.0.set_duration(Duration::from_secs_f32(2.));
+        },
+    );
+}
+
+const SPAWN_PLACEMENT_ATTEMPTS: usize = 16;
+/// radius that has to be free of colliders around a spawn point
+const SPAWN_CLEARANCE: f32 = 16.;
+fn pick_spawn_point(
+    level: &LevelInfo,
+    entropy: &mut EntropyComponent<WyRand>,
+    players: &[Vec2],
+    spatial_query: &SpatialQuery,
+) -> Option<Vec2> {
+    // an arena narrower than the clearance only has its middle line left
+    let min = (level.arena.min + SPAWN_CLEARANCE).min(level.arena.center());
+    let max = (level.arena.max - SPAWN_CLEARANCE).max(level.arena.center());
+    (0..SPAWN_PLACEMENT_ATTEMPTS).find_map(|_| {
+        let point = Vec2::new(
+            entropy.gen_range(min.x..=max.x),
+            entropy.gen_range(min.y..=max.y),
+        );
+        let near_player = players
+            .iter()
+            .any(|player| player.distance(point) < level.spawn_safe_distance);
+        let occupied = !spatial_query
+            .shape_intersections(
+                &Collider::ball(SPAWN_CLEARANCE),
+                point,
+                0.,
+                SpatialQueryFilter::default(),
+            )
+            .is_empty();
+        (!near_player && !occupied).then_some(point)
+    })
+}
+
+/// Marks the place where an enemy is about to appear.
+/// The enemy itself does not exist until the timer finishes,
+/// so it can neither be hit nor deal damage meanwhile.
+#[derive(Component)]
+pub struct SpawnWarning {
+    pub timer: Timer,
+    pub enemy: String,
+    pub class: EnemyClass,
+    pub affixes: Vec<elites::EliteAffix>,
+}
+
+/// Every enemy of `level` appears through one of these, bosses and summons included
+pub fn spawn_warning(
+    commands: &mut Commands,
+    position: Vec2,
+    warning: SpawnWarning,
+    level: Entity,
+) -> Entity {
+    let size = match warning.class {
+        EnemyClass::Normal(_) => 32.,
+        EnemyClass::Boss => 64.,
+    };
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(1.0, 0.1, 0.1, 0.5),
+                    custom_size: Some(Vec2::splat(size)),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(position.extend(0.)),
+                ..Default::default()
+            },
+            warning,
+            LevelRef(level),
+        ))
+        .id()
+}
+
+const SPAWN_WARNING_BLINK_PER_SECOND: f32 = 8.;
+pub fn spawn_warning_system(
+    mut commands: Commands,
+    mut warnings: Query<(
+        Entity,
+        &mut SpawnWarning,
+        &mut Visibility,
+        &Transform,
+        &LevelRef,
+    )>,
+    registry: Res<EnemyRegistry>,
+    time: Res<Time>,
+) {
+    warnings.for_each_mut(
+        |(entity, mut warning, mut visibility, transform, LevelRef(level))| {
+            if warning.timer.tick(time.delta()).just_finished() {
+                commands.entity(entity).despawn();
+                let class = match warning.class {
+                    EnemyClass::Normal(class) => class,
+                    EnemyClass::Boss => 1,
+                };
+                let enemy = match spawn_enemy(
+                    &mut commands,
+                    &registry,
+                    &warning.enemy,
+                    class,
+                    *transform,
+                    *level,
+                ) {
+                    Ok(enemy) => enemy,
+                    Err(err) => {
+                        warn!("{err}, spawn is dropped");
+                        return;
+                    }
+                };
+                if warning.class == EnemyClass::Boss {
+                    // bosses don't scale with tiers
+                    commands
+                        .entity(enemy)
+                        .remove::<NormalClass>()
+                        .insert(BossClass);
                 }
-            }
-            _ => {
-                if spawn_timer.0.duration() != Duration::from_secs_f32(6.) {
-                    spawn_timer.0.reset();
-                    spawn_timer.0.set_duration(Duration::from_secs_f32(6.));
+                if !warning.affixes.is_empty() {
+                    commands
+                        .entity(enemy)
+                        .insert(elites::EliteAffixes(warning.affixes.clone()));
                 }
+            } else if (warning.timer.elapsed_secs() * SPAWN_WARNING_BLINK_PER_SECOND) as u32 % 2
+                == 0
+            {
+                *visibility = Visibility::Inherited;
+            } else {
+                *visibility = Visibility::Hidden;
             }
-        };
+        },
+    );
+}
+
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    registry: &EnemyRegistry,
+    enemy: &str,
+    class: u32,
+    transform: Transform,
+    level: Entity,
+) -> Result<Entity, UnknownEnemyType> {
+    let enemy_type = registry.get(enemy)?;
+    let mut enemy_commands = commands.spawn((
+        enemies::Archetype(enemy.into()),
+        NormalClass(class),
+        Enemy,
+        Character,
+        InitPosition(transform),
+        LevelRef(level),
+        // archetypes tune it in their initializer
+        steering::Steering::default(),
+        line_of_sight::LineOfSight::default(),
+    ));
+    enemy_type.insert(&mut enemy_commands);
+    Ok(enemy_commands.id())
+}
+
+/// Static obstacle, `size` is the full extent of its cuboid collider
+#[derive(Component)]
+pub struct Wall {
+    pub size: Vec2,
+}
+impl Wall {
+    /// Normal of the side a box of `half_size` at `position` went in the least,
+    /// and how deep it is, when it overlaps this wall centered at `center`
+    pub fn penetration(
+        &self,
+        center: Vec2,
+        position: Vec2,
+        half_size: Vec2,
+    ) -> Option<(Vec2, f32)> {
+        let offset = position - center;
+        let overlap = self.size / 2. + half_size - offset.abs();
+        if overlap.x <= 0. || overlap.y <= 0. {
+            return None;
+        }
+        Some(if overlap.x < overlap.y {
+            (Vec2::new(offset.x.signum(), 0.), overlap.x)
+        } else {
+            (Vec2::new(0., offset.y.signum()), overlap.y)
+        })
+    }
+}
+
+pub fn spawn_wall(commands: &mut Commands, center: Vec2, size: Vec2) -> Entity {
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::DARK_GRAY,
+                    custom_size: Some(size),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(center.extend(0.)),
+                ..Default::default()
+            },
+            Wall { size },
+            RigidBody::Static,
+            Collider::cuboid(size.x, size.y),
+            CollisionLayers::new([Layer::Wall], [Layer::Player, Layer::Enemy]),
+        ))
+        .id()
+}
+
+/// characters are left overlapping walls by this much so their contacts still register
+const WALL_SKIN: f32 = 0.5;
+
+/// Characters move by their transform, so nothing stops them at walls but this
+pub fn wall_blocking_system(
+    mut movers: Query<(&mut Transform, &ColliderAabb), With<Character>>,
+    walls: Query<(&Transform, &Wall), Without<Character>>,
+) {
+    movers.for_each_mut(|(mut transform, aabb)| {
+        let half_extents = aabb.half_extents();
+        let mover_half = Vec2::new(half_extents.x, half_extents.y);
+        walls.for_each(|(wall_transform, wall)| {
+            let Some((normal, depth)) = wall.penetration(
+                wall_transform.translation.truncate(),
+                transform.translation.truncate(),
+                mover_half,
+            ) else {
+                return;
+            };
+            transform.translation += (normal * (depth - WALL_SKIN).max(0.)).extend(0.);
+        });
     });
 }
 
+const WALL_THICKNESS: f32 = 20.;
+/// Surrounds the arena with walls just outside of it
+pub fn spawn_arena_walls(commands: &mut Commands, arena: Rect) {
+    let center = arena.center();
+    let half = arena.half_size() + WALL_THICKNESS / 2.;
+    let horizontal = Vec2::new(arena.width() + 2. * WALL_THICKNESS, WALL_THICKNESS);
+    let vertical = Vec2::new(WALL_THICKNESS, arena.height());
+    spawn_wall(commands, center + Vec2::new(0., half.y), horizontal);
+    spawn_wall(commands, center - Vec2::new(0., half.y), horizontal);
+    spawn_wall(commands, center + Vec2::new(half.x, 0.), vertical);
+    spawn_wall(commands, center - Vec2::new(half.x, 0.), vertical);
+}
+
 #[derive(Component)]
 pub struct BossSpawnTimer(Timer);
 pub fn level_boss_spawner(
     mut commands: Commands,
-    mut levels: Query<(Entity, &mut LevelInfo, Option<&mut BossSpawnTimer>)>,
+    mut levels: Query<(
+        Entity,
+        &mut LevelInfo,
+        &mut EntropyComponent<WyRand>,
+        Option<&mut BossSpawnTimer>,
+    )>,
+    players: Query<&Transform, (With<Player>, With<Character>)>,
+    spatial_query: SpatialQuery,
     time: Res<Time>,
 ) {
-    levels.for_each_mut(|(entity, mut level, boss_timer)| {
+    let player_positions = players
+        .iter()
+        .map(|transform| transform.translation.truncate())
+        .collect::<Vec<_>>();
+    levels.for_each_mut(|(entity, mut level, mut entropy, boss_timer)| {
         if let Some(mut boss_timer) = boss_timer {
-            if boss_timer.0.tick(time.delta()).just_finished() {
-                // TODO: spawn all bosses
+            let bosses_left = level
+                .enemy_to_spawn
+                .iter()
+                .any(|e| e.class == EnemyClass::Boss && e.amount > 0);
+            if !boss_timer.0.tick(time.delta()).finished() || !bosses_left {
+                return;
             }
+            // like any enemy, bosses wait for a safe place away from players
+            let Some(spawn_point) =
+                pick_spawn_point(&level, &mut entropy, &player_positions, &spatial_query)
+            else {
+                return;
+            };
+            let warning_time = level.spawn_warning_time;
+            level
+                .enemy_to_spawn
+                .iter_mut()
+                .filter(|e| e.class == EnemyClass::Boss)
+                .for_each(|desc| {
+                    for _ in 0..std::mem::take(&mut desc.amount) {
+                        spawn_warning(
+                            &mut commands,
+                            spawn_point,
+                            SpawnWarning {
+                                timer: Timer::from_seconds(warning_time, TimerMode::Once),
+                                enemy: desc.enemy.clone(),
+                                class: EnemyClass::Boss,
+                                affixes: vec![],
+                            },
+                            entity,
+                        );
+                    }
+                });
         } else if level
             .enemy_to_spawn
             .iter()
             .any(|e| e.class == EnemyClass::Boss)
-            && level.enemy_to_spawn.iter().map(|e| e.amount).sum::<u32>() == 0
+            && level
+                .enemy_to_spawn
+                .iter()
+                .filter(|e| e.class != EnemyClass::Boss)
+                .map(|e| e.amount)
+                .sum::<u32>()
+                == 0
         {
             commands
                 .entity(entity)
@@ -0,0 +1,63 @@
+use crate::*;
+use enemy_targeting::HostileTarget;
+
+/// Whether an enemy sees its target, and where it saw it last
+#[derive(Component, Default)]
+pub struct LineOfSight {
+    pub visible: bool,
+    pub last_known: Option<Vec2>,
+}
+
+/// enemies within the same cell share their raycast toward a target
+const SIGHT_CELL_SIZE: f32 = 32.;
+/// the last known position is searched until the enemy gets this close
+const SEARCH_REACHED: f32 = 16.;
+
+pub fn line_of_sight_system(
+    mut viewers: Query<(&mut LineOfSight, &Transform, Option<&HostileTarget>), With<Enemy>>,
+    targets: Query<&Transform, With<Life>>,
+    spatial_query: SpatialQuery,
+    mut cache: Local<HashMap<(IVec2, Entity), bool>>,
+) {
+    cache.clear();
+    viewers.for_each_mut(|(mut sight, transform, target)| {
+        let position = transform.translation.truncate();
+        let Some((target, target_position)) = target.and_then(|HostileTarget(target)| {
+            targets
+                .get(*target)
+                .ok()
+                .map(|target_transform| (*target, target_transform.translation.truncate()))
+        }) else {
+            sight.visible = false;
+            return;
+        };
+
+        let cell = (position / SIGHT_CELL_SIZE).floor().as_ivec2();
+        let visible = *cache.entry((cell, target)).or_insert_with(|| {
+            let offset = target_position - position;
+            let Some(direction) = offset.try_normalize() else {
+                return true;
+            };
+            spatial_query
+                .cast_ray(
+                    position,
+                    direction,
+                    offset.length(),
+                    true,
+                    SpatialQueryFilter::new().with_masks([Layer::Wall]),
+                )
+                .is_none()
+        });
+
+        sight.visible = visible;
+        if visible {
+            sight.last_known = Some(target_position);
+        } else if sight
+            .last_known
+            .is_some_and(|point| point.distance(position) <= SEARCH_REACHED)
+        {
+            // nothing there, go back to hunting
+            sight.last_known = None;
+        }
+    });
+}
@@ -16,17 +16,29 @@ use bevy_cursor::prelude::*;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use bevy_rand::prelude::*;
 use bevy_xpbd_2d::prelude::*;
-use enemies::{normal_enemy, EnemyType};
+use enemies::{normal_enemy, EnemyRegistryExt};
 use enemy_targeting::{AimTargetingType, MoveTargetingType};
+use game_state::{GameState, GameplaySet};
 use levels::*;
 use movements::Movement;
 use rand::prelude::*;
 
+mod behaviors;
+mod bosses;
 pub(crate) mod bullets;
 mod constants;
+mod director;
+mod elites;
 mod enemy_targeting;
 mod forced_moving;
+mod game_state;
+mod health;
 mod input_handling;
+mod line_of_sight;
+mod navigation;
+mod pickups;
+mod steering;
+mod upgrades;
 use bullets::*;
 use input_handling::KeyboardControlled;
 
@@ -53,18 +65,125 @@ fn main() {
         .register_type::<bullets::explode_shot::ExplodeShot>()
         .register_type::<bullets::splash_shot::SplashShot>()
         .register_type::<bullets::lazer_shot::LazerShot>()
+        .register_type::<bullets::friendly_fire::FriendlyFire>()
+        .register_type::<bullets::burning::Burning>()
+        .register_type::<bullets::knockback::Knockback>()
         // register for Character entity components
         .register_type::<Player>()
         .register_type::<Enemy>()
         .register_type::<Character>()
         .register_type::<Life>()
+        .register_type::<health::MaxLife>()
+        .register_type::<health::Armor>()
+        .register_type::<health::Shield>()
         .register_type::<movements::Movable>()
+        .register_type::<MoveTargetingType>()
+        .register_type::<AimTargetingType>()
+        .register_type::<enemies::BallCollider>()
         .register_type::<Bullet>()
+        .register_type::<enemies::DamageMultiplier>()
+        .register_type::<enemies::Reward>()
+        .register_type::<enemies::FrontalShield>()
+        .register_type::<enemies::ContactDamage>()
+        .register_type::<steering::Steering>()
+        .register_type::<behaviors::Behavior>()
+        .register_type::<behaviors::BehaviorState>()
+        .register_type::<behaviors::BehaviorTransition>()
+        .register_type::<behaviors::BehaviorAction>()
+        .register_type::<behaviors::BehaviorCondition>()
+        .register_type::<Vec<behaviors::BehaviorState>>()
+        .register_type::<Vec<behaviors::BehaviorTransition>>()
+        .add_state::<GameState>()
+        .configure_sets(Update, GameplaySet.run_if(in_state(GameState::InRun)))
+        .configure_sets(FixedUpdate, GameplaySet.run_if(in_state(GameState::InRun)))
+        .init_resource::<game_state::RunProgress>()
+        .init_resource::<game_state::TransitionTimer>()
+        .init_resource::<Score>()
+        .init_resource::<steering::EnemyGrid>()
+        .init_resource::<navigation::Navigation>()
+        .init_resource::<enemies::EnemyTierTable>()
+        .init_resource::<upgrades::UpgradePool>()
+        .init_resource::<upgrades::UpgradeDraft>()
+        .register_enemy(normal_enemy::NormalEnemy)
+        .register_enemy(enemies::ranged_enemy::RangedEnemy)
+        .register_enemy(enemies::charger_enemy::ChargerEnemy)
+        .register_enemy(enemies::kamikaze_enemy::KamikazeEnemy)
+        .register_enemy(enemies::shield_enemy::ShieldEnemy)
+        .register_enemy(bosses::warden())
+        .register_enemy_prefab("base", "entities/base_enemy.scn.ron")
         .add_event::<bullets::BulletSpawnEvent>()
         .add_event::<bullets::BulletSucceedEvent>()
         .add_event::<BulletHitEvent>()
+        .add_event::<director::DirectorEvent>()
+        .add_event::<DeathEvent>()
+        .add_event::<behaviors::BehaviorTransitionEvent>()
+        .add_event::<bosses::BossPhaseEvent>()
+        .add_event::<health::DamageEvent>()
+        .add_event::<health::DamageDealt>()
         // .add_plugins(space_editor::SpaceEditorPlugin::default())
-        .add_systems(Startup, (setup_camera, spawn_player))
+        .add_systems(Startup, setup_camera)
+        .add_systems(Update, game_state::state_input_system)
+        .add_systems(OnEnter(GameState::MainMenu), game_state::main_menu_ui)
+        .add_systems(OnExit(GameState::MainMenu), game_state::despawn_state_ui)
+        .add_systems(
+            OnEnter(GameState::Paused),
+            (game_state::paused_ui, game_state::pause_time),
+        )
+        .add_systems(
+            OnExit(GameState::Paused),
+            (game_state::despawn_state_ui, game_state::resume_time),
+        )
+        .add_systems(
+            OnEnter(GameState::Draft),
+            (upgrades::draft_ui, game_state::pause_time),
+        )
+        .add_systems(
+            OnExit(GameState::Draft),
+            (game_state::despawn_state_ui, game_state::resume_time),
+        )
+        .add_systems(
+            Update,
+            upgrades::draft_input_system.run_if(in_state(GameState::Draft)),
+        )
+        .add_systems(OnEnter(GameState::GameOver), game_state::game_over_ui)
+        .add_systems(OnExit(GameState::GameOver), game_state::despawn_state_ui)
+        // a new run starts whenever the menu or game over screen is left
+        .add_systems(
+            OnExit(GameState::MainMenu),
+            (game_state::reset_run, spawn_player).chain(),
+        )
+        .add_systems(
+            OnExit(GameState::GameOver),
+            (game_state::reset_run, spawn_player).chain(),
+        )
+        .add_systems(
+            OnEnter(GameState::LevelTransition),
+            (
+                game_state::clear_level,
+                spawn_level,
+                game_state::level_transition_ui,
+            )
+                .chain(),
+        )
+        .add_systems(
+            OnExit(GameState::LevelTransition),
+            game_state::despawn_state_ui,
+        )
+        .add_systems(
+            Update,
+            game_state::transition_timer_system.run_if(in_state(GameState::LevelTransition)),
+        )
+        .add_systems(
+            Update,
+            (
+                upgrades::level_up_system,
+                // a cleared level or a game over wins over a draft, drafts wait for the next level
+                game_state::level_cleared_system.after(upgrades::level_up_system),
+                game_state::game_over_system.after(game_state::level_cleared_system),
+            )
+                .after(life_dies_system)
+                .in_set(GameplaySet),
+        )
         .add_systems(
             Update,
             (
@@ -73,17 +192,28 @@ fn main() {
                 life_dies_system,
                 movements::move_system.before(bullet_before_despawn),
                 aim_system,
+                enemies::frontal_shield_break_system,
+                enemies::shield_reflect_system,
                 player_enemy_shock_system,
+                contact_damage_system,
                 forced_moving::forced_move_system,
+                levels::wall_blocking_system
+                    .after(movements::move_system)
+                    .after(forced_moving::forced_move_system),
+                (forced_moving::dash_system, forced_moving::dash_timer_system).chain(),
                 (
                     forced_moving::shock_system,
                     forced_moving::shock_timer_system,
                 )
                     .chain(),
-            ),
+            )
+                .in_set(GameplaySet),
+        )
+        .add_systems(
+            Update,
+            (cooldown_system, shoot_system).chain().in_set(GameplaySet),
         )
-        .add_systems(Update, (cooldown_system, shoot_system).chain())
-        .add_systems(Update, randomize_weapons)
+        .add_systems(Update, randomize_weapons.in_set(GameplaySet))
         .add_systems(
             Update,
             (
@@ -102,7 +232,31 @@ fn main() {
                 bullets::splash_shot::splash_shot_bullet_initializer,
                 bullets::lazer_shot::lazer_shot_move_system,
                 bullets::lazer_shot::lazer_shot_bullet_initializer,
-            ),
+            )
+                .in_set(GameplaySet),
+        )
+        .add_systems(
+            Update,
+            (
+                health::max_life_system,
+                health::damage_system
+                    .after(hit_damage_system)
+                    .after(contact_damage_system)
+                    .after(enemies::charger_enemy::charger_system)
+                    .before(life_dies_system),
+                health::shield_regen_system,
+                health::damage_log_system.after(health::damage_system),
+                elites::vampiric_system.after(health::damage_system),
+                enemy_targeting::damage_threat_system
+                    .after(health::damage_system)
+                    .before(enemy_targeting::enemy_aggro_system),
+                bullets::burning::ignite_system,
+                bullets::burning::burn_system.before(health::damage_system),
+                bullets::knockback::knockback_system,
+                pickups::experience_drop_system.after(life_dies_system),
+                pickups::experience_orb_system.before(upgrades::level_up_system),
+            )
+                .in_set(GameplaySet),
         )
         .add_systems(
             Update,
@@ -111,26 +265,84 @@ fn main() {
                 bullets::bullet_lifetime,
                 bullets::bullet_before_despawn,
             )
-                .chain(),
+                .chain()
+                .in_set(GameplaySet),
         )
         .add_systems(
             Update,
-            (levels::level_enemy_spawner, levels::level_boss_spawner),
+            (
+                levels::level_enemy_spawner,
+                levels::level_boss_spawner,
+                levels::spawn_warning_system,
+                director::director_system,
+                director::director_log_system,
+                behaviors::behavior_log_system,
+                bosses::boss_phase_log_system,
+                bosses::boss_health_bar_system,
+                invulnerable_system,
+                pickups::health_pickup_system,
+            )
+                .in_set(GameplaySet),
         )
-        .add_systems(Update, (enemies::normal_enemy::normal_enemy_initializer))
         .add_systems(
             Update,
             (
+                enemies::normal_enemy::normal_enemy_initializer,
+                enemies::ranged_enemy::ranged_enemy_initializer,
+                enemies::charger_enemy::charger_enemy_initializer,
+                enemies::charger_enemy::charger_system,
+                enemies::kamikaze_enemy::kamikaze_enemy_initializer,
+                enemies::shield_enemy::shield_enemy_initializer,
+                enemies::kamikaze_enemy::fuse_system
+                    .before(enemies::explode_on_death_system)
+                    .before(life_dies_system),
+                enemies::prefab_enemy::prefab_enemy_initializer,
+                enemies::ball_collider_system,
+                enemies::enemy_tier_system,
+                enemies::spawn_on_death_system
+                    .after(health::damage_system)
+                    .before(life_dies_system),
+                elites::elite_initializer,
+                enemies::explode_on_death_system
+                    .after(health::damage_system)
+                    .before(life_dies_system),
+                elites::teleporting_system,
+                bosses::boss_initializer,
+                bosses::boss_system,
+            )
+                .in_set(GameplaySet),
+        )
+        .add_systems(
+            Update,
+            (
+                behaviors::behavior_system.before(enemy_targeting::move_targeting_system),
+                navigation::nav_grid_system,
+                navigation::flow_field_system
+                    .after(navigation::nav_grid_system)
+                    .after(line_of_sight::line_of_sight_system)
+                    .before(enemy_targeting::move_targeting_system),
+                line_of_sight::line_of_sight_system
+                    .before(enemy_targeting::move_targeting_system)
+                    .before(enemy_targeting::aim_targeting_system)
+                    .before(enemy_targeting::fire_control_system),
+                enemy_targeting::flank_slot_system.before(enemy_targeting::move_targeting_system),
                 enemy_targeting::move_targeting_system,
+                steering::enemy_grid_system,
+                steering::steering_system
+                    .after(steering::enemy_grid_system)
+                    .after(enemy_targeting::move_targeting_system)
+                    .before(movements::move_system),
                 enemy_targeting::aim_targeting_system,
-                enemy_targeting::enemy_search_nearist_player,
+                enemy_targeting::enemy_aggro_system,
+                enemy_targeting::fire_control_system,
             )
-                .before(life_dies_system),
+                .before(life_dies_system)
+                .in_set(GameplaySet),
         )
         .insert_resource(Time::<Fixed>::from_hz(
             constants::GAME_FIXED_TICK_PER_SECOND,
         ))
-        .add_systems(FixedUpdate, bullet_hit_system)
+        .add_systems(FixedUpdate, bullet_hit_system.in_set(GameplaySet))
         .run();
 }
 
@@ -143,30 +355,108 @@ fn setup_camera(mut commands: Commands) {
 
 #[derive(Component, Reflect)]
 struct Player;
-#[derive(Component, Reflect)]
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
 struct Enemy;
-#[derive(Component, Reflect)]
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
 struct Character;
 
-#[derive(Component, Reflect)]
-struct Life(i32);
-fn life_dies_system(mut commands: Commands, mut query: Query<(Entity, &Life)>) {
-    query.for_each_mut(|(entity, life)| {
-        if life.0 <= 0 {
-            commands.entity(entity).despawn();
+#[derive(Component, Reflect, Default)]
+#[reflect(Component)]
+struct Life(f32);
+fn life_dies_system(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &Life,
+        &Transform,
+        Option<&LastHitBy>,
+        Option<&enemies::Archetype>,
+        Option<&enemies::Reward>,
+        Option<&enemies::kamikaze_enemy::Fuse>,
+    )>,
+    players: Query<(), With<Player>>,
+    mut score: ResMut<Score>,
+    mut writer: EventWriter<DeathEvent>,
+) {
+    query.for_each_mut(
+        |(entity, life, transform, last_hit_by, archetype, reward, fuse)| {
+            if life.0 <= 0. {
+                // only players kill, blowing itself up or a kamikaze blast is not a kill
+                let killer = last_hit_by
+                    .map(|LastHitBy(killer)| *killer)
+                    .filter(|killer| players.contains(*killer))
+                    .filter(|_| !fuse.is_some_and(|fuse| fuse.timer.finished()));
+                let reward = reward.filter(|_| killer.is_some());
+                if let Some(reward) = reward {
+                    score.0 += reward.score;
+                }
+                writer.send(DeathEvent {
+                    position: transform.translation.truncate(),
+                    killer,
+                    archetype: archetype.map(|enemies::Archetype(archetype)| archetype.clone()),
+                    experience: reward.map_or(0, |reward| reward.experience),
+                });
+                // weapons go along with their holder
+                commands.entity(entity).despawn_recursive();
+            }
+        },
+    );
+}
+
+/// Sent when an entity runs out of life, the entity is despawned at the same time
+#[derive(Event, Debug)]
+struct DeathEvent {
+    position: Vec2,
+    /// the player who dealt the last hit, none when it died another way
+    killer: Option<Entity>,
+    /// only enemies have an archetype
+    archetype: Option<String>,
+    /// dropped as an orb where the entity died
+    experience: u32,
+}
+
+/// Whoever damaged this entity the last time
+#[derive(Component)]
+struct LastHitBy(Entity);
+
+/// Takes no damage until the timer finishes
+#[derive(Component)]
+struct Invulnerable(Timer);
+
+/// Seconds of [`Invulnerable`] granted every time this is hurt
+#[derive(Component)]
+struct HurtCooldown(f32);
+
+fn invulnerable_system(
+    mut commands: Commands,
+    mut invulnerables: Query<(Entity, &mut Invulnerable)>,
+    time: Res<Time>,
+) {
+    invulnerables.for_each_mut(|(entity, mut invulnerable)| {
+        if invulnerable.0.tick(time.delta()).just_finished() {
+            commands.entity(entity).remove::<Invulnerable>();
         }
     });
 }
 
+#[derive(Resource, Default)]
+struct Score(u32);
+
 #[derive(PhysicsLayer)]
 enum Layer {
     Player,
     Enemy,
     PlayerBullet,
     EnemyBullet,
+    Pickup,
+    Wall,
+    /// bullets that hit characters of both sides
+    Blast,
 }
 
-fn spawn_player(mut commands: Commands, mut global_entropy: ResMut<GlobalEntropy<WyRand>>) {
+fn spawn_player(mut commands: Commands) {
     commands
         .spawn((
             SpriteBundle {
@@ -180,14 +470,21 @@ fn spawn_player(mut commands: Commands, mut global_entropy: ResMut<GlobalEntropy
                 },
                 ..Default::default()
             },
-            Life(100),
+            Life(constants::PLAYER_MAX_LIFE),
+            health::MaxLife(constants::PLAYER_MAX_LIFE),
             movements::Movable { speed: 300.0 },
             Player,
             Character,
             KeyboardControlled,
+            forced_moving::DashAbility::default(),
+            HurtCooldown(0.6),
+            upgrades::Experience::default(),
             Collider::ball(16.),
             // RigidBody::Kinematic,
-            CollisionLayers::new([Layer::Player], [Layer::Enemy, Layer::EnemyBullet]),
+            CollisionLayers::new(
+                [Layer::Player],
+                [Layer::Enemy, Layer::EnemyBullet, Layer::Pickup, Layer::Wall],
+            ),
         ))
         .with_children(|cb| {
             cb.spawn((
@@ -242,23 +539,83 @@ fn spawn_player(mut commands: Commands, mut global_entropy: ResMut<GlobalEntropy
                 Player,
             ));
         });
-
-    spawn_level(commands, global_entropy);
 }
 
-fn spawn_level(mut commands: Commands, mut global_entropy: ResMut<GlobalEntropy<WyRand>>) {
+fn spawn_level(
+    mut commands: Commands,
+    mut global_entropy: ResMut<GlobalEntropy<WyRand>>,
+    progress: Res<game_state::RunProgress>,
+) {
+    let arena = Rect::from_center_size(Vec2::ZERO, Vec2::new(600., 600.));
+    levels::spawn_arena_walls(&mut commands, arena);
+    levels::spawn_wall(&mut commands, Vec2::new(-150., 100.), Vec2::new(160., 20.));
+    levels::spawn_wall(&mut commands, Vec2::new(150., -100.), Vec2::new(20., 160.));
     commands.spawn((
         LevelInfo {
-            id: 0,
-            enemy_to_spawn: vec![EnemyDescriptor {
-                enemy: normal_enemy::NormalEnemy::TEXT.into(),
-                class: EnemyClass::Normal(1),
-                amount: 10,
-            }],
+            id: progress.level,
+            enemy_to_spawn: vec![
+                EnemyDescriptor {
+                    enemy: normal_enemy::NormalEnemy::TEXT.into(),
+                    class: EnemyClass::Normal(1),
+                    amount: 10,
+                },
+                EnemyDescriptor {
+                    enemy: enemies::ranged_enemy::RangedEnemy::TEXT.into(),
+                    class: EnemyClass::Normal(1),
+                    amount: 4,
+                },
+                EnemyDescriptor {
+                    enemy: enemies::charger_enemy::ChargerEnemy::TEXT.into(),
+                    class: EnemyClass::Normal(1),
+                    amount: 3,
+                },
+                EnemyDescriptor {
+                    enemy: enemies::kamikaze_enemy::KamikazeEnemy::TEXT.into(),
+                    class: EnemyClass::Normal(1),
+                    amount: 4,
+                },
+                EnemyDescriptor {
+                    enemy: enemies::shield_enemy::ShieldEnemy::TEXT.into(),
+                    class: EnemyClass::Normal(1),
+                    amount: 3,
+                },
+                EnemyDescriptor {
+                    enemy: "base".into(),
+                    class: EnemyClass::Normal(2),
+                    amount: 3,
+                },
+                EnemyDescriptor {
+                    enemy: "warden".into(),
+                    class: EnemyClass::Boss,
+                    amount: 1,
+                },
+            ],
             is_spawning: true,
             wave_enemy_limit: 3,
+            arena,
+            spawn_safe_distance: 200.,
+            spawn_warning_time: 1.,
+            elite_chance: 0.15,
+            pacing: SpawnPacing {
+                interval: PacingCurve(vec![(0., 1.2), (1., 0.6), (3., 0.4)]),
+                crowd: PacingCurve(vec![(1., 1.), (1., 3.3), (2., 3.3), (2., 10.)]),
+                player_health: PacingCurve(vec![(0.3, 2.), (0.7, 1.)]),
+                batch: PacingCurve(vec![(1., 1.), (3., 2.)]),
+            },
         },
         NextSpawnTimer(Timer::from_seconds(1., TimerMode::Repeating)),
+        LevelTime::default(),
+        director::Director::new(director::DirectorSettings {
+            window: 10.,
+            decision_interval: 2.,
+            min_pressure: 0.5,
+            max_pressure: 2.,
+            pressure_step: 0.25,
+            max_extra_enemies: 5,
+            extra_enemy: normal_enemy::NormalEnemy::TEXT.into(),
+            relief_heal: 30.,
+            relief_cooldown: 20.,
+        }),
         global_entropy.fork_rng(),
     ));
     // commands.spawn((
@@ -301,7 +658,8 @@ fn randomize_weapons(
         commands.entity(entity).insert(global.fork_rng());
     })
 }
-#[derive(Component, Reflect, Clone, Copy)]
+#[derive(Component, Reflect, Default, Clone, Copy)]
+#[reflect(Component)]
 struct Aims(Vec2);
 
 #[derive(Component, Reflect)]
@@ -321,6 +679,7 @@ impl Default for Weapon {
     }
 }
 
+#[derive(Clone)]
 struct BulletLoader {
     bullet: Bullet,
     bullet_type: Box<dyn BulletType>,
@@ -354,7 +713,8 @@ mod movements {
         PointMove(Vec2),
     }
 
-    #[derive(Component, Reflect)]
+    #[derive(Component, Reflect, Default)]
+    #[reflect(Component)]
     pub(crate) struct Movable {
         pub(crate) speed: f32,
     }
@@ -436,9 +796,20 @@ mod movements {
 
 pub(crate) fn player_enemy_shock_system(
     mut commands: Commands,
-    colliding: Query<(Entity, &CollidingEntities, &Transform), With<Character>>,
+    colliding: Query<
+        (
+            Entity,
+            &CollidingEntities,
+            &Transform,
+            Option<&forced_moving::Dashing>,
+        ),
+        With<Character>,
+    >,
 ) {
-    colliding.for_each(|(entity, colliding_entities, transform)| {
+    colliding.for_each(|(entity, colliding_entities, transform, dashing)| {
+        if dashing.is_some() {
+            return;
+        }
         let Some(c_entity) = colliding_entities
             .iter()
             .filter(|e| colliding.contains(**e))
@@ -459,6 +830,49 @@ pub(crate) fn player_enemy_shock_system(
     })
 }
 
+/// Players lose life touching enemies, enemies are not hurt by the same contact
+fn contact_damage_system(
+    players: Query<
+        (Entity, &CollidingEntities),
+        (With<Player>, With<Character>, Without<Invulnerable>),
+    >,
+    enemies: Query<
+        (
+            &enemies::ContactDamage,
+            Option<&enemies::DamageMultiplier>,
+            Option<&enemies::charger_enemy::ChargerState>,
+        ),
+        With<Enemy>,
+    >,
+    mut writer: EventWriter<health::DamageEvent>,
+) {
+    players.for_each(|(entity, colliding_entities)| {
+        // only the hardest hitting contact counts
+        let Some((enemy, damage)) = colliding_entities
+            .iter()
+            .filter_map(|e| {
+                let (contact, multiplier, charger_state) = enemies.get(*e).ok()?;
+                // a dashing charger already deals its dash damage
+                if matches!(
+                    charger_state,
+                    Some(enemies::charger_enemy::ChargerState::Dash { .. })
+                ) {
+                    return None;
+                }
+                Some((*e, contact.0 * multiplier.map_or(1., |m| m.0)))
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        else {
+            return;
+        };
+        writer.send(health::DamageEvent {
+            target: entity,
+            source: Some(enemy),
+            amount: damage,
+        });
+    });
+}
+
 fn aim_system(mut query: Query<(&Aims, &mut Transform)>) {
     query.for_each_mut(|(aim, mut transform)| {
         transform.rotation = Quat::from_rotation_z(
@@ -469,7 +883,12 @@ fn aim_system(mut query: Query<(&Aims, &mut Transform)>) {
 
 fn shoot_system(
     weapons: Query<(Entity, &Weapon, &Parent), (With<IsShooting>, Without<IsCoolingdown>)>,
-    weapon_holder: Query<(Entity, &Transform, &Children)>,
+    weapon_holder: Query<(
+        Entity,
+        &Transform,
+        &Children,
+        Option<&enemies::DamageMultiplier>,
+    )>,
     mut commands: Commands,
     mut writer: EventWriter<bullets::BulletSpawnEvent>,
 ) {
@@ -480,12 +899,17 @@ fn shoot_system(
             bullet_extras,
         }) = weapon.loads.first()
         {
-            let owner_transform = weapon_holder.get(owner.get()).unwrap().1;
+            let (_, owner_transform, _, damage_multiplier) =
+                weapon_holder.get(owner.get()).unwrap();
+            let mut bullet = *bullet;
+            if let Some(enemies::DamageMultiplier(multiplier)) = damage_multiplier {
+                bullet.damage *= multiplier;
+            }
             writer.send(bullets::BulletSpawnEvent {
                 shooter: *owner_transform,
                 by: owner.get(),
                 with: entity,
-                bullet: *bullet,
+                bullet,
                 bullet_type: bullet_type.clone(),
                 bullet_extras: bullet_extras.clone(),
                 generation: 0,
@@ -524,15 +948,14 @@ fn bullet_hit_system(
         &mut HitCount,
         &Transform,
     )>,
-    hitable: Query<(Entity, &mut Life)>,
+    hitable: Query<(Entity, &Transform, Option<&enemies::FrontalShield>), With<Life>>,
     mut writer: EventWriter<BulletHitEvent>,
 ) {
     bullets.for_each_mut(
         |(bullet_entity, colliding_entities, bullet, mut hit_count, transform)| {
             if !colliding_entities.is_empty() {
-                hitable
-                    .iter_many(colliding_entities.iter())
-                    .for_each(|(entity, _)| {
+                hitable.iter_many(colliding_entities.iter()).for_each(
+                    |(entity, target_transform, shield)| {
                         let count = *hit_count
                             .0
                             .entry(entity)
@@ -540,14 +963,28 @@ fn bullet_hit_system(
                             .or_insert(1);
 
                         if count as f32 <= bullet.hit_limit {
+                            let kind = match shield {
+                                Some(shield)
+                                    if shield.covers(target_transform, transform.translation) =>
+                                {
+                                    if shield.reflect {
+                                        HitKind::Reflected
+                                    } else {
+                                        HitKind::Blocked
+                                    }
+                                }
+                                _ => HitKind::Damaging,
+                            };
                             writer.send(BulletHitEvent {
                                 bullet_entity,
                                 target: entity,
                                 bullet_transform: *transform,
                                 first_hit: count == 1,
+                                kind,
                             });
                         }
-                    });
+                    },
+                );
             }
         },
     );
@@ -559,6 +996,16 @@ struct BulletHitEvent {
     target: Entity,
     bullet_transform: Transform,
     first_hit: bool,
+    kind: HitKind,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum HitKind {
+    Damaging,
+    /// stopped by a shield, the bullet is gone
+    Blocked,
+    /// sent back by a shield, the bullet keeps going
+    Reflected,
 }
 
 fn bullet_hit_endurance_system(
@@ -582,17 +1029,30 @@ fn bullet_hit_endurance_system(
         let (WeaponRef(weapon_entity), mut endurance, transform, BulletGeneration(generation)) =
             bullets.get_mut(event.bullet_entity).unwrap();
 
-        let (mut entropy, _) = weapons.get_mut(*weapon_entity).unwrap();
+        match event.kind {
+            HitKind::Damaging => {}
+            HitKind::Blocked => {
+                endurance.0 = -1.;
+                return;
+            }
+            HitKind::Reflected => return,
+        }
 
-        succeed_event_writer.send(bullets::BulletSucceedEvent {
-            weapon: *weapon_entity,
-            generation: *generation,
-            bullet: event.bullet_entity,
-            transform: *transform,
-        });
+        // bullets may outlive the weapon they were shot with, or have none
+        let weapon = weapons.get_mut(*weapon_entity).ok();
+        if weapon.is_some() {
+            succeed_event_writer.send(bullets::BulletSucceedEvent {
+                weapon: *weapon_entity,
+                generation: *generation,
+                bullet: event.bullet_entity,
+                transform: *transform,
+            });
+        }
 
         if endurance.0 < 1. {
-            if !entropy.gen_bool(endurance.0.into()) {
+            let survives =
+                weapon.is_some_and(|(mut entropy, _)| entropy.gen_bool(endurance.0.into()));
+            if !survives {
                 endurance.0 = -1.;
             }
         } else {
@@ -603,13 +1063,22 @@ fn bullet_hit_endurance_system(
 
 fn hit_damage_system(
     mut reader: EventReader<BulletHitEvent>,
-    mut life: Query<(Entity, &mut Life)>,
-    bullets: Query<(Entity, &Bullet)>,
+    mut frontal_shields: Query<&mut enemies::FrontalShield>,
+    bullets: Query<(&Bullet, &Shooter)>,
+    mut writer: EventWriter<health::DamageEvent>,
 ) {
     reader.read().for_each(|event| {
-        if let Ok((_, mut life)) = life.get_mut(event.target) {
-            let dmg = bullets.get(event.bullet_entity).unwrap().1.damage;
-            life.0 -= dmg.ceil() as i32;
+        let (bullet, Shooter(shooter)) = bullets.get(event.bullet_entity).unwrap();
+        if event.kind != HitKind::Damaging {
+            if let Ok(mut shield) = frontal_shields.get_mut(event.target) {
+                shield.durability -= bullet.damage;
+            }
+            return;
         }
+        writer.send(health::DamageEvent {
+            target: event.target,
+            source: Some(*shooter),
+            amount: bullet.damage,
+        });
     });
 }
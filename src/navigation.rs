@@ -0,0 +1,227 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use bevy::utils::HashSet;
+
+use crate::*;
+
+const CELL_SIZE: f32 = 20.;
+/// walls are grown by this much so enemies don't scrape along them
+const CLEARANCE: f32 = 12.;
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+/// Walkable cells covering the bounds of all walls
+pub struct NavGrid {
+    origin: Vec2,
+    size: IVec2,
+    blocked: Vec<bool>,
+}
+impl NavGrid {
+    fn build(walls: &[Rect]) -> Option<Self> {
+        let bounds = walls.iter().copied().reduce(|a, b| a.union(b))?;
+        let size = (bounds.size() / CELL_SIZE).ceil().as_ivec2();
+        let mut grid = Self {
+            origin: bounds.min,
+            size,
+            blocked: vec![false; (size.x * size.y) as usize],
+        };
+        walls.iter().for_each(|wall| {
+            let wall = wall.inset(CLEARANCE);
+            let min = grid.cell(wall.min).max(IVec2::ZERO);
+            let max = grid.cell(wall.max).min(size - IVec2::ONE);
+            (min.x..=max.x)
+                .flat_map(|x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+                .for_each(|cell| {
+                    let index = grid.index(cell).unwrap();
+                    grid.blocked[index] = true;
+                });
+        });
+        Some(grid)
+    }
+
+    fn cell(&self, position: Vec2) -> IVec2 {
+        ((position - self.origin) / CELL_SIZE).floor().as_ivec2()
+    }
+
+    fn center(&self, cell: IVec2) -> Vec2 {
+        self.origin + (cell.as_vec2() + 0.5) * CELL_SIZE
+    }
+
+    fn index(&self, cell: IVec2) -> Option<usize> {
+        (cell.cmpge(IVec2::ZERO).all() && cell.cmplt(self.size).all())
+            .then(|| (cell.y * self.size.x + cell.x) as usize)
+    }
+
+    fn walkable(&self, cell: IVec2) -> bool {
+        self.index(cell).is_some_and(|index| !self.blocked[index])
+    }
+
+    /// Walkable neighbors with their cost, diagonals can't cut corners
+    fn neighbors(&self, cell: IVec2) -> impl Iterator<Item = (IVec2, u32)> + '_ {
+        (-1..=1)
+            .flat_map(|x| (-1..=1).map(move |y| IVec2::new(x, y)))
+            .filter(|offset| *offset != IVec2::ZERO)
+            .filter_map(move |offset| {
+                let next = cell + offset;
+                if !self.walkable(next) {
+                    return None;
+                }
+                if offset.x != 0 && offset.y != 0 {
+                    let corners_open = self.walkable(cell + IVec2::new(offset.x, 0))
+                        && self.walkable(cell + IVec2::new(0, offset.y));
+                    return corners_open.then_some((next, DIAGONAL_COST));
+                }
+                Some((next, STRAIGHT_COST))
+            })
+    }
+
+    /// Whether walking straight from `from` to `to` stays off blocked cells
+    pub fn line_clear(&self, from: Vec2, to: Vec2) -> bool {
+        let steps = (from.distance(to) / (CELL_SIZE / 2.)).ceil() as usize;
+        (0..=steps).all(|step| {
+            let cell = self.cell(from.lerp(to, step as f32 / steps.max(1) as f32));
+            // outside of the grid is open ground
+            self.index(cell).map_or(true, |index| !self.blocked[index])
+        })
+    }
+}
+
+/// Cost to reach a goal cell from every cell of the grid
+pub struct FlowField {
+    goal: IVec2,
+    costs: Vec<u32>,
+}
+impl FlowField {
+    fn build(grid: &NavGrid, goal: IVec2) -> Self {
+        let mut costs = vec![u32::MAX; grid.blocked.len()];
+        let mut open = BinaryHeap::new();
+        if let Some(index) = grid.index(goal) {
+            costs[index] = 0;
+            open.push(Reverse((0, goal.x, goal.y)));
+        }
+        while let Some(Reverse((cost, x, y))) = open.pop() {
+            let cell = IVec2::new(x, y);
+            if cost > costs[grid.index(cell).unwrap()] {
+                continue;
+            }
+            grid.neighbors(cell).for_each(|(next, step)| {
+                let index = grid.index(next).unwrap();
+                if cost + step < costs[index] {
+                    costs[index] = cost + step;
+                    open.push(Reverse((cost + step, next.x, next.y)));
+                }
+            });
+        }
+        Self { goal, costs }
+    }
+
+    fn cost(&self, grid: &NavGrid, cell: IVec2) -> u32 {
+        grid.index(cell).map_or(u32::MAX, |index| self.costs[index])
+    }
+}
+
+/// Grid built from [`Wall`]s, one flow field per player
+/// and one per cell where a player was last seen
+#[derive(Resource, Default)]
+pub struct Navigation {
+    grid: Option<NavGrid>,
+    fields: HashMap<Entity, FlowField>,
+    search_fields: HashMap<IVec2, FlowField>,
+}
+impl Navigation {
+    /// Where to head for to reach `goal`, going around walls toward `target` if needed
+    pub fn route(&self, from: Vec2, goal: Vec2, target: Entity) -> Vec2 {
+        self.step(from, goal, self.fields.get(&target))
+    }
+
+    /// Where to head for to reach the point a hidden target was last seen at
+    pub fn search(&self, from: Vec2, last_known: Vec2) -> Vec2 {
+        let field = self
+            .grid
+            .as_ref()
+            .and_then(|grid| self.search_fields.get(&grid.cell(last_known)));
+        self.step(from, last_known, field)
+    }
+
+    fn step(&self, from: Vec2, goal: Vec2, field: Option<&FlowField>) -> Vec2 {
+        let (Some(grid), Some(field)) = (&self.grid, field) else {
+            return goal;
+        };
+        if grid.line_clear(from, goal) {
+            return goal;
+        }
+        let cell = grid.cell(from);
+        grid.neighbors(cell)
+            .map(|(next, _)| next)
+            .min_by_key(|next| field.cost(grid, *next))
+            .filter(|next| field.cost(grid, *next) != u32::MAX)
+            .map_or(goal, |next| grid.center(next))
+    }
+}
+
+/// Rebuilds the grid whenever walls are added, moved or removed
+pub fn nav_grid_system(
+    mut navigation: ResMut<Navigation>,
+    walls: Query<(&Transform, &Wall)>,
+    changed: Query<(), (With<Wall>, Or<(Added<Wall>, Changed<Transform>)>)>,
+    mut removed: RemovedComponents<Wall>,
+) {
+    let removed = removed.read().count() > 0;
+    if changed.is_empty() && !removed {
+        return;
+    }
+    let walls = walls
+        .iter()
+        .map(|(transform, Wall { size })| {
+            Rect::from_center_size(transform.translation.truncate(), *size)
+        })
+        .collect::<Vec<_>>();
+    navigation.grid = NavGrid::build(&walls);
+    navigation.fields.clear();
+    navigation.search_fields.clear();
+}
+
+/// Recomputes the field of a player when it moves to another cell,
+/// and keeps one field for every last known position still searched
+pub fn flow_field_system(
+    mut navigation: ResMut<Navigation>,
+    players: Query<(Entity, &Transform), (With<Player>, With<Character>)>,
+    sights: Query<&line_of_sight::LineOfSight>,
+) {
+    let navigation = &mut *navigation;
+    let Some(grid) = &navigation.grid else {
+        return;
+    };
+    navigation
+        .fields
+        .retain(|target, _| players.contains(*target));
+    players.for_each(|(entity, transform)| {
+        let goal = grid.cell(transform.translation.truncate());
+        if navigation
+            .fields
+            .get(&entity)
+            .is_some_and(|field| field.goal == goal)
+        {
+            return;
+        }
+        navigation
+            .fields
+            .insert(entity, FlowField::build(grid, goal));
+    });
+
+    let searched = sights
+        .iter()
+        .filter(|sight| !sight.visible)
+        .filter_map(|sight| sight.last_known)
+        .map(|point| grid.cell(point))
+        .collect::<HashSet<_>>();
+    navigation
+        .search_fields
+        .retain(|goal, _| searched.contains(goal));
+    searched.into_iter().for_each(|goal| {
+        navigation
+            .search_fields
+            .entry(goal)
+            .or_insert_with(|| FlowField::build(grid, goal));
+    });
+}
@@ -0,0 +1,83 @@
+use crate::*;
+
+#[derive(Component)]
+pub(crate) struct HealthPickup(pub f32);
+
+pub(crate) const HEALTH_PICKUP_RADIUS: f32 = 6.;
+
+pub(crate) fn spawn_health_pickup(commands: &mut Commands, position: Vec2, heal: f32) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::LIME_GREEN,
+                custom_size: Some(Vec2::splat(HEALTH_PICKUP_RADIUS * 2.)),
+                ..Default::default()
+            },
+            transform: Transform::from_translation(position.extend(0.)),
+            ..Default::default()
+        },
+        HealthPickup(heal),
+        Collider::ball(HEALTH_PICKUP_RADIUS),
+        Sensor,
+        CollisionLayers::new([Layer::Pickup], [Layer::Player]),
+    ));
+}
+
+pub(crate) fn health_pickup_system(
+    mut commands: Commands,
+    pickups: Query<(Entity, &CollidingEntities, &HealthPickup)>,
+    mut players: Query<(&mut Life, Option<&health::MaxLife>), (With<Player>, With<Character>)>,
+) {
+    pickups.for_each(|(entity, colliding_entities, HealthPickup(heal))| {
+        let Some(player) = colliding_entities.iter().find(|e| players.contains(**e)) else {
+            return;
+        };
+        let (mut life, max_life) = players.get_mut(*player).unwrap();
+        let max_life = max_life.map_or(constants::PLAYER_MAX_LIFE, |m| m.0);
+        life.0 = (life.0 + heal).min(max_life);
+        commands.entity(entity).despawn();
+    });
+}
+
+/// Experience dropped by a dying enemy, gained by the player walking over it
+#[derive(Component)]
+pub(crate) struct ExperienceOrb(pub u32);
+
+pub(crate) fn spawn_experience_orb(commands: &mut Commands, position: Vec2, experience: u32) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::AQUAMARINE,
+                custom_size: Some(Vec2::new(8.0, 8.0)),
+                ..Default::default()
+            },
+            transform: Transform::from_translation(position.extend(0.)),
+            ..Default::default()
+        },
+        ExperienceOrb(experience),
+        Collider::ball(8.),
+        Sensor,
+        CollisionLayers::new([Layer::Pickup], [Layer::Player]),
+    ));
+}
+
+pub(crate) fn experience_drop_system(mut commands: Commands, mut deaths: EventReader<DeathEvent>) {
+    deaths
+        .read()
+        .filter(|death| death.experience > 0)
+        .for_each(|death| spawn_experience_orb(&mut commands, death.position, death.experience));
+}
+
+pub(crate) fn experience_orb_system(
+    mut commands: Commands,
+    orbs: Query<(Entity, &CollidingEntities, &ExperienceOrb)>,
+    mut players: Query<&mut upgrades::Experience, (With<Player>, With<Character>)>,
+) {
+    orbs.for_each(|(entity, colliding_entities, ExperienceOrb(experience))| {
+        let Some(player) = colliding_entities.iter().find(|e| players.contains(**e)) else {
+            return;
+        };
+        players.get_mut(*player).unwrap().gain(*experience);
+        commands.entity(entity).despawn();
+    });
+}
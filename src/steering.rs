@@ -0,0 +1,149 @@
+use crate::*;
+
+/// How much an enemy bends its movement around others and walls,
+/// a weight of 0 turns the behavior off
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct Steering {
+    /// keeps away from enemies within `separation_radius`
+    pub separation: f32,
+    pub separation_radius: f32,
+    /// goes along with nearby enemies
+    pub alignment: f32,
+    /// turns away from walls within `avoid_distance` ahead
+    pub avoidance: f32,
+    pub avoid_distance: f32,
+}
+impl Default for Steering {
+    fn default() -> Self {
+        Self {
+            separation: 1.5,
+            separation_radius: 40.,
+            alignment: 0.2,
+            avoidance: 2.,
+            avoid_distance: 40.,
+        }
+    }
+}
+
+const CELL_SIZE: f32 = 64.;
+
+/// Enemies bucketed by position, rebuilt every frame
+#[derive(Resource, Default)]
+pub struct EnemyGrid {
+    cells: HashMap<IVec2, Vec<(Entity, Vec2, Vec2)>>,
+}
+impl EnemyGrid {
+    fn cell(position: Vec2) -> IVec2 {
+        (position / CELL_SIZE).floor().as_ivec2()
+    }
+
+    /// `(entity, position, velocity)` of enemies that may be within `radius` of `position`
+    pub fn nearby(
+        &self,
+        position: Vec2,
+        radius: f32,
+    ) -> impl Iterator<Item = &(Entity, Vec2, Vec2)> {
+        let min = Self::cell(position - Vec2::splat(radius));
+        let max = Self::cell(position + Vec2::splat(radius));
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+    }
+}
+
+pub fn enemy_grid_system(
+    mut grid: ResMut<EnemyGrid>,
+    enemies: Query<(Entity, &Transform, Option<&LinearVelocity>), (With<Enemy>, With<Character>)>,
+) {
+    grid.cells.values_mut().for_each(Vec::clear);
+    enemies.for_each(|(entity, transform, velocity)| {
+        let position = transform.translation.truncate();
+        grid.cells
+            .entry(EnemyGrid::cell(position))
+            .or_default()
+            .push((entity, position, velocity.map_or(Vec2::ZERO, |v| v.0)));
+    });
+}
+
+/// Blends separation, alignment and wall avoidance into whatever movement targeting asked for
+pub fn steering_system(
+    grid: Res<EnemyGrid>,
+    mut steerers: Query<(
+        Entity,
+        &Steering,
+        &Transform,
+        &movements::Movable,
+        &mut Movement,
+    )>,
+    walls: Query<(&Transform, &Wall)>,
+    time: Res<Time>,
+) {
+    steerers.for_each_mut(|(entity, steering, transform, movable, mut movement)| {
+        let position = transform.translation.truncate();
+        let (desired, point_distance) = match *movement {
+            Movement::DirectionMove(direction) => (direction, None),
+            Movement::PointMove(point) => {
+                let offset = point - position;
+                // close enough to arrive this frame, leave it as it is
+                if offset.length() <= movable.speed * time.delta_seconds() {
+                    return;
+                }
+                (offset.normalize(), Some(offset.length()))
+            }
+        };
+        if desired == Vec2::ZERO {
+            return;
+        }
+
+        let mut separation = Vec2::ZERO;
+        let mut alignment = Vec2::ZERO;
+        grid.nearby(position, steering.separation_radius)
+            .filter(|(other, _, _)| *other != entity)
+            .for_each(|(_, other_position, other_velocity)| {
+                let offset = position - *other_position;
+                let distance = offset.length();
+                if distance >= steering.separation_radius {
+                    return;
+                }
+                // stacked right on top of each other, split them by index
+                let away = offset
+                    .try_normalize()
+                    .unwrap_or_else(|| Vec2::from_angle(entity.index() as f32));
+                separation += away * (1. - distance / steering.separation_radius);
+                alignment += other_velocity.normalize_or_zero();
+            });
+
+        let ahead = position + desired * steering.avoid_distance;
+        let avoidance = walls
+            .iter()
+            .filter_map(|(wall_transform, Wall { size })| {
+                let center = wall_transform.translation.truncate();
+                let half = *size / 2.;
+                let to_ahead = ahead - center;
+                if to_ahead.x.abs() > half.x || to_ahead.y.abs() > half.y {
+                    return None;
+                }
+                // push out of the side facing the enemy
+                let from = position - center;
+                Some(if from.x.abs() / half.x > from.y.abs() / half.y {
+                    Vec2::X * from.x.signum()
+                } else {
+                    Vec2::Y * from.y.signum()
+                })
+            })
+            .sum::<Vec2>();
+
+        let steered = desired
+            + separation * steering.separation
+            + alignment.normalize_or_zero() * steering.alignment
+            + avoidance * steering.avoidance;
+        let steered = steered.try_normalize().unwrap_or(desired);
+        // point moves keep their distance so they still stop on arrival
+        *movement = match point_distance {
+            Some(distance) => Movement::PointMove(position + steered * distance),
+            None => Movement::DirectionMove(steered),
+        };
+    });
+}
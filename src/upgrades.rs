@@ -0,0 +1,303 @@
+use crate::*;
+use bullets::{
+    burning::Burning, explode_shot::ExplodeShot, knockback::Knockback, splash_shot::SplashShot,
+};
+use game_state::GameState;
+
+/// Experience of a player, every level gained is a draft to pick an upgrade from
+#[derive(Component, Default)]
+pub struct Experience {
+    pub points: u32,
+    pub level: u32,
+    /// level ups whose draft hasn't been picked yet
+    pub pending_drafts: u32,
+}
+impl Experience {
+    /// points needed to go from `level` to the next one
+    pub fn required(level: u32) -> u32 {
+        20 + 15 * level
+    }
+
+    pub fn gain(&mut self, points: u32) {
+        self.points += points;
+        while self.points >= Self::required(self.level) {
+            self.points -= Self::required(self.level);
+            self.level += 1;
+            self.pending_drafts += 1;
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Rarity {
+    Common,
+    Rare,
+    Epic,
+}
+impl Rarity {
+    fn weight(self) -> f64 {
+        match self {
+            Rarity::Common => 60.,
+            Rarity::Rare => 30.,
+            Rarity::Epic => 10.,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) enum Upgrade {
+    /// multiplies the damage of every load of the weapon
+    Damage(f32),
+    /// multiplies how fast the weapon cools down
+    FireRate(f32),
+    /// added to every load of the weapon
+    Extra(Box<dyn BulletExtra>),
+    /// appended to the weapon chain, fired from where the last load hits
+    Load(BulletLoader),
+    MaxLife(f32),
+    MoveSpeed(f32),
+    Armor(f32),
+    /// adds to the shield layer, or grants one
+    Shield(f32),
+    Vampirism(f32),
+    DashCharge,
+}
+impl Upgrade {
+    fn apply_to_weapon(&self, weapon: &mut Weapon) {
+        match self {
+            Upgrade::Damage(multiplier) => weapon
+                .loads
+                .iter_mut()
+                .for_each(|load| load.bullet.damage *= multiplier),
+            Upgrade::FireRate(multiplier) => weapon.accelerate *= multiplier,
+            Upgrade::Extra(extra) => weapon.loads.iter_mut().for_each(|load| {
+                // the same extra twice does nothing more
+                load.bullet_extras
+                    .retain(|e| e.as_any().type_id() != extra.as_any().type_id());
+                load.bullet_extras.push(extra.clone());
+            }),
+            Upgrade::Load(load) => weapon.loads.push(load.clone()),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct UpgradeOffer {
+    pub name: &'static str,
+    pub rarity: Rarity,
+    pub upgrade: Upgrade,
+}
+
+/// Every upgrade a draft can offer
+#[derive(Resource)]
+pub(crate) struct UpgradePool(pub Vec<UpgradeOffer>);
+impl Default for UpgradePool {
+    fn default() -> Self {
+        let offer = |name, rarity, upgrade| UpgradeOffer {
+            name,
+            rarity,
+            upgrade,
+        };
+        Self(vec![
+            offer("sharpened lens", Rarity::Common, Upgrade::Damage(1.15)),
+            offer("overclock", Rarity::Common, Upgrade::FireRate(1.2)),
+            offer("vitality", Rarity::Common, Upgrade::MaxLife(20.)),
+            offer("light boots", Rarity::Common, Upgrade::MoveSpeed(1.1)),
+            offer("plating", Rarity::Common, Upgrade::Armor(1.)),
+            offer(
+                "incendiary",
+                Rarity::Rare,
+                Upgrade::Extra(Box::new(Burning {
+                    damage_per_second: 8.,
+                    seconds: 2.,
+                })),
+            ),
+            offer(
+                "concussive",
+                Rarity::Rare,
+                Upgrade::Extra(Box::new(Knockback { impact: 0.6 })),
+            ),
+            offer("energy shield", Rarity::Rare, Upgrade::Shield(30.)),
+            offer("second wind", Rarity::Rare, Upgrade::DashCharge),
+            offer(
+                "shrapnel",
+                Rarity::Rare,
+                Upgrade::Load(BulletLoader {
+                    bullet: Bullet {
+                        life_time: 0.3,
+                        endurance: 1.,
+                        hit_limit: 1.,
+                        speed: 800.,
+                        cooldown: 0.,
+                        damage: 8.,
+                    },
+                    bullet_type: Box::new(SplashShot {
+                        count: 3,
+                        angle: 0.6,
+                    }),
+                    bullet_extras: vec![],
+                }),
+            ),
+            offer(
+                "blast core",
+                Rarity::Epic,
+                Upgrade::Load(BulletLoader {
+                    bullet: Bullet {
+                        life_time: 0.1,
+                        endurance: INFINITY,
+                        hit_limit: 1.,
+                        speed: 0.,
+                        cooldown: 0.,
+                        damage: 30.,
+                    },
+                    bullet_type: Box::new(ExplodeShot),
+                    bullet_extras: vec![],
+                }),
+            ),
+            offer("bloodthirst", Rarity::Epic, Upgrade::Vampirism(0.05)),
+            offer("focused beam", Rarity::Epic, Upgrade::Damage(1.4)),
+        ])
+    }
+}
+
+/// Offers shown while in [`GameState::Draft`], picked for `player`
+#[derive(Resource, Default)]
+pub(crate) struct UpgradeDraft {
+    player: Option<Entity>,
+    offers: Vec<UpgradeOffer>,
+}
+
+const DRAFT_SIZE: usize = 3;
+const SHIELD_REGEN_PART: f32 = 0.2;
+const SHIELD_REGEN_DELAY: f32 = 3.;
+
+/// Opens a draft for the first player with a level up left to pick
+pub(crate) fn level_up_system(
+    players: Query<(Entity, &Experience), (With<Player>, With<Character>)>,
+    pool: Res<UpgradePool>,
+    mut draft: ResMut<UpgradeDraft>,
+    mut global: ResMut<GlobalEntropy<WyRand>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some((player, _)) = players
+        .iter()
+        .find(|(_, experience)| experience.pending_drafts > 0)
+    else {
+        return;
+    };
+    // rolled from the global entropy so a seeded run gets the same offers
+    let Ok(offers) = pool
+        .0
+        .choose_multiple_weighted(&mut *global, DRAFT_SIZE, |offer| offer.rarity.weight())
+    else {
+        return;
+    };
+    *draft = UpgradeDraft {
+        player: Some(player),
+        offers: offers.cloned().collect(),
+    };
+    next_state.set(GameState::Draft);
+}
+
+pub(crate) fn draft_ui(mut commands: Commands, draft: Res<UpgradeDraft>) {
+    let offers = draft
+        .offers
+        .iter()
+        .enumerate()
+        .map(|(i, offer)| format!("{}: {} ({:?})", i + 1, offer.name, offer.rarity))
+        .collect::<Vec<_>>()
+        .join("\n");
+    game_state::spawn_state_text(&mut commands, format!("level up!\n{offers}"));
+}
+
+const DRAFT_KEYS: [KeyCode; DRAFT_SIZE] = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3];
+
+pub(crate) fn draft_input_system(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    mut draft: ResMut<UpgradeDraft>,
+    mut players: Query<(
+        &mut Experience,
+        &mut Life,
+        &mut health::MaxLife,
+        &mut movements::Movable,
+        &mut forced_moving::DashAbility,
+        Option<&mut health::Armor>,
+        Option<&mut health::Shield>,
+        Option<&mut elites::Vampiric>,
+    )>,
+    mut weapons: Query<(&mut Weapon, &Parent)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(offer) = DRAFT_KEYS
+        .iter()
+        .position(|key| input.just_pressed(*key))
+        .and_then(|index| draft.offers.get(index))
+    else {
+        return;
+    };
+    next_state.set(GameState::InRun);
+    // the player died or left while picking
+    let Some((
+        player,
+        Ok((
+            mut experience,
+            mut life,
+            mut max_life,
+            mut movable,
+            mut dash,
+            armor,
+            shield,
+            vampiric,
+        )),
+    )) = draft.player.map(|player| (player, players.get_mut(player)))
+    else {
+        return;
+    };
+    experience.pending_drafts = experience.pending_drafts.saturating_sub(1);
+    info!("upgrade: {}", offer.name);
+
+    match &offer.upgrade {
+        Upgrade::MaxLife(amount) => {
+            max_life.0 += amount;
+            life.0 += amount;
+        }
+        Upgrade::MoveSpeed(multiplier) => movable.speed *= multiplier,
+        Upgrade::Armor(amount) => match armor {
+            Some(mut armor) => armor.0 += amount,
+            None => {
+                commands.entity(player).insert(health::Armor(*amount));
+            }
+        },
+        Upgrade::Shield(amount) => match shield {
+            Some(mut shield) => {
+                shield.max += amount;
+                shield.amount += amount;
+                shield.regen = shield.max * SHIELD_REGEN_PART;
+            }
+            None => {
+                commands.entity(player).insert(health::Shield::new(
+                    *amount,
+                    amount * SHIELD_REGEN_PART,
+                    SHIELD_REGEN_DELAY,
+                ));
+            }
+        },
+        Upgrade::Vampirism(ratio) => match vampiric {
+            Some(mut vampiric) => vampiric.0 += ratio,
+            None => {
+                commands.entity(player).insert(elites::Vampiric(*ratio));
+            }
+        },
+        Upgrade::DashCharge => {
+            dash.max_charges += 1;
+            dash.charges += 1;
+        }
+        weapon_upgrade => weapons
+            .iter_mut()
+            .filter(|(_, parent)| parent.get() == player)
+            .for_each(|(mut weapon, _)| weapon_upgrade.apply_to_weapon(&mut weapon)),
+    }
+    draft.offers.clear();
+}